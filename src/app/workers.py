@@ -8,12 +8,13 @@ class FileTreeWorker(QThread):
     finished = pyqtSignal()  # Signal emitted when building completes
     error = pyqtSignal(str)  # Signal emitted if an error occurs
     
-    def __init__(self, mod_manager, file_range, conflict_check_range, max_workers):
+    def __init__(self, mod_manager, file_range, conflict_check_range, max_workers, include_disabled=False):
         super().__init__()
         self.mod_manager = mod_manager
         self.file_range = file_range
         self.conflict_check_range = conflict_check_range
         self.max_workers = max_workers
+        self.include_disabled = include_disabled
     
     def run(self):
         """Build file tree in background thread"""
@@ -22,7 +23,8 @@ class FileTreeWorker(QThread):
             self.mod_manager.build_file_tree(
                 file_range=self.file_range,
                 conflict_check_range=self.conflict_check_range,
-                process_max_workers=self.max_workers
+                process_max_workers=self.max_workers,
+                include_disabled=self.include_disabled,
             )
             self.finished.emit()
         except Exception as e:
@@ -187,6 +187,59 @@ def verify_utf8_bom(file_path: Union[str, Path]) -> bool:
         return False
 
 
+def detect_line_format(source: bytes) -> tuple[str, str]:
+    """
+    Detect the line ending style and indentation unit of a file's content.
+
+    Args:
+        source: Raw file content
+
+    Returns:
+        Tuple of (line_ending, indent_unit), e.g. ("\\r\\n", "\\t") or ("\\n", "    ")
+    """
+    crlf = source.count(b"\r\n")
+    lf = source.count(b"\n") - crlf
+    line_ending = "\r\n" if crlf > lf else "\n"
+    indent_unit = "\t"
+    space_widths = []
+    for line in source.splitlines():
+        if line.startswith(b"\t"):
+            break
+        width = len(line) - len(line.lstrip(b" "))
+        if width and line.strip():
+            space_widths.append(width)
+    else:
+        if space_widths:
+            indent_unit = " " * min(space_widths)
+    return line_ending, indent_unit
+
+
+def find_encoding_issues(source: bytes, suffix: str) -> list[str]:
+    """
+    Find encoding problems in a file's content that CK3 may silently mishandle.
+
+    Args:
+        source: Raw file content
+        suffix: File suffix, e.g. ".yml"; localization files require a UTF-8 BOM, script and gui files must not have one
+
+    Returns:
+        List of issue descriptions, empty if none
+    """
+    issues = []
+    has_bom = source.startswith(b"\xef\xbb\xbf")
+    if suffix.lower() == ".yml" and not has_bom:
+        issues.append("missing UTF-8 BOM")
+    elif suffix.lower() in (".txt", ".gui") and has_bom:
+        issues.append("unexpected UTF-8 BOM")
+    if source.find(b"\xef\xbb\xbf", 3 if has_bom else 0) != -1:
+        issues.append("UTF-8 BOM inside the content")
+    try:
+        source.decode("utf-8")
+    except UnicodeDecodeError as e:
+        issues.append(f"non UTF-8 bytes at offset {e.start}")
+    return issues
+
+
 if __name__ == "__main__":
     # Example usage
     import sys
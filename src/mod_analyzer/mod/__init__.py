@@ -1,6 +1,6 @@
-from .descriptor import Mod
+from .descriptor import Mod, classify_outdated, find_near_duplicates, resolve_load_order, find_dependency_cycles, assign_dup_ids
 from .mod_list import ModList, DefinitionNode, DefinitionDirectoryNode, DefinitionFileNode, ModList, SourceList, SourceEntry
-from .manager import ModManager
+from .manager import ModManager, ModManagerConfig, ExtractionCancelled
 from .mod_loader import (
     locate_mod_from_file,
     parse_paradox_mod_descriptor,
@@ -11,7 +11,11 @@ from .mod_loader import (
     get_all_mod_descriptors,
     get_enabled_mod_dirs,
     get_enabled_mod_descriptors,
+    read_dlc_load,
+    write_dlc_load,
     get_playset_mod_dirs,
     get_playset_mod_descriptors,
+    export_mods_json,
+    import_mods_json,
     file_search_recursive,
 )
\ No newline at end of file
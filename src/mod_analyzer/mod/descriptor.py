@@ -3,10 +3,85 @@
 This module contains the ModDescriptor class, which represents metadata
 about a CK3 mod from its descriptor.mod file.
 """
+import re
+import heapq
+import hashlib
+import logging
 from pathlib import Path
 from typing import Optional, List
-from dataclasses import dataclass, asdict, field
+from dataclasses import dataclass, field, fields
 CK3_DOC_DIR = Path.home()/"Documents"/"Paradox Interactive"/"Crusader Kings III"
+pkg = (__package__ or __name__).split('.')[0]
+logger = logging.getLogger(pkg)
+# "quoted \"string\"", braces, `=`, bare words; `#` comments are matched to be skipped
+DESCRIPTOR_TOKEN_PATTERN = re.compile(r'#[^\n]*|"((?:[^"\\]|\\.)*)"|([{}=])|([^\s{}="#]+)')
+DESCRIPTOR_LIST_KEYS = ("tags", "replaces", "dependencies")
+
+def parse_descriptor_text(text: str) -> dict[str, str|List[str]|dict[str, str]]:
+    """Parses the content of a descriptor.mod file.
+    
+    Scalars are returned as strings, `{ "a" "b" }` blocks as lists and `{ k=v }` blocks as dicts.\
+        A UTF-8 BOM, CRLF line endings and `#` comments are tolerated.
+    Raises:
+        ValueError: If the content is malformed, e.g. an unclosed block.
+    """
+    tokens = []
+    for match in DESCRIPTOR_TOKEN_PATTERN.finditer(text.lstrip("\ufeff")):
+        quoted, symbol, word = match.groups()
+        if quoted is not None:
+            tokens.append(("value", re.sub(r'\\(.)', r'\1', quoted)))
+        elif symbol is not None:
+            tokens.append((symbol, symbol))
+        elif word is not None:
+            tokens.append(("value", word))
+    
+    def parse_block(pos: int) -> tuple[list|dict, int]:
+        items, pairs = [], {}
+        while pos < len(tokens) and tokens[pos][0] != "}":
+            kind, value = tokens[pos]
+            if kind != "value":
+                raise ValueError(f"Unexpected {value!r} in descriptor block")
+            if pos + 1 < len(tokens) and tokens[pos+1][0] == "=":
+                inner, pos = parse_value(pos + 2)
+                pairs[value] = inner
+            else:
+                items.append(value)
+                pos += 1
+        if pos >= len(tokens):
+            raise ValueError("Unclosed block in descriptor")
+        return (pairs if pairs else items), pos + 1
+    
+    def parse_value(pos: int) -> tuple[str|list|dict, int]:
+        if pos >= len(tokens):
+            raise ValueError("Missing value at the end of descriptor")
+        kind, value = tokens[pos]
+        if kind == "{":
+            return parse_block(pos + 1)
+        if kind != "value":
+            raise ValueError(f"Unexpected {value!r} in descriptor")
+        return value, pos + 1
+    
+    result: dict = {}
+    replace_paths = []
+    pos = 0
+    while pos < len(tokens):
+        kind, key = tokens[pos]
+        if kind != "value" or pos + 1 >= len(tokens) or tokens[pos+1][0] != "=":
+            raise ValueError(f"Expected `key = value` in descriptor, got {key!r}")
+        result[key], pos = parse_value(pos + 2)
+        if key == "replace_path": # may be repeated, one line per replaced directory
+            replace_paths.append(result[key])
+    if replace_paths:
+        result["replace_path"] = replace_paths[0]
+        result["replace_paths"] = replace_paths
+    for key in DESCRIPTOR_LIST_KEYS:
+        value = result.get(key, [])
+        result[key] = [value] if isinstance(value, str) else list(value)
+    return result
+
+def parse_descriptor(path: str|Path) -> dict[str, str|List[str]|dict[str, str]]:
+    """Reads and parses a descriptor.mod file, see `parse_descriptor_text`."""
+    return parse_descriptor_text(Path(path).read_bytes().decode("utf-8-sig").replace("\r\n", "\n"))
 
 @dataclass(order=True) 
 class Mod:
@@ -22,9 +97,15 @@ class Mod:
         supported_version (Optional[int]): Supported game version.
         remote_file_id (Optional[str]): Remote file ID for Steam Workshop mods.
         picture (Optional[Path]): Path to the mod picture.
-        replace_path (Optional[Path]): Path that this mod replaces.
+        replace_path (Optional[Path]): Path that this mod replaces, the first one if there are several.
+        replace_paths (List[Path]): All paths that this mod replaces, descriptors repeat `replace_path` for each.
         replaces (List[str]): List of mod names that this mod replaces.
         dependencies (List[str]): List of mod dependencies.
+        is_archive (bool): Whether the mod is a zip archive, declared by the legacy `archive` key instead of `path`.
+        root_dir (Optional[Path]): Directory containing the mod folder, overrides the global mods/workshop dirs.
+        user_dir (Optional[str]): Separate user directory for saves and settings, launcher v2 `user_dir` key.
+        short_description (Optional[str]): Launcher v2 `short_description` key.
+        game_custom_data (dict[str, str]): Launcher v2 `game_custom_data` block, e.g. {"multiplayer_synchronized": "yes"}.
     """
     # include _sort_index in dataclass comparison
     _sort_index: int = field(init=False, repr=False, compare=True)
@@ -39,9 +120,15 @@ class Mod:
     remote_file_id: Optional[str] = field(default="", repr=False, compare=False)  # Required for Steam Workshop mods
     picture: Optional[Path] = field(default_factory=Path, repr=False, compare=False)
     replace_path: Optional[Path] = field(default_factory=Path, repr=False, compare=False)
+    replace_paths: List[Path] = field(default_factory=list, repr=False, compare=False)
     replaces: List[str] = field(default_factory=list, repr=False, compare=False)
     dependencies: List[str] = field(default_factory=list, repr=False, compare=False)
     file: Optional[Path] = field(default=None, repr=False, compare=False)  # Path to descriptor.mod file
+    is_archive: bool = field(default=False, repr=False, compare=False)
+    root_dir: Optional[Path] = field(default=None, repr=False, compare=False)
+    user_dir: Optional[str] = field(default=None, repr=False, compare=False)
+    short_description: Optional[str] = field(default=None, repr=False, compare=False)
+    game_custom_data: dict[str, str] = field(default_factory=dict, repr=False, compare=False)
     # If this is True, enabled mods sort before disabled mods
     _enabled_first: bool = field(default = False, init=True, repr=False, compare=False)
     _dup_id:int = field(default=0, init=False, repr=False, compare=False)
@@ -54,8 +141,10 @@ class Mod:
         if name == "enabled" and self._enabled_first:
             object.__setattr__(self, "_sort_index", 0 if bool(value) else 1)
 
-        if name in {"path", "picture", "replace_path", "file"} and value is not None:
+        if name in {"path", "picture", "replace_path", "file", "root_dir"} and value is not None:
             value = Path(value)  # ensure Path object
+        elif name == "replace_paths" and value is not None:
+            value = [Path(v) for v in value]
         super().__setattr__(name, value)
     @property
     def dup_name(self) -> str:
@@ -64,27 +153,77 @@ class Mod:
             return f"{self.name}#{self._dup_id}"
         return self.name
     def as_dict(self):
-        """Convert to dictionary representation."""
-        return asdict(self)
+        """Convert to dictionary representation.
+        
+        Includes all public fields and `dup_id`. Paths are converted to strings, so the result is JSON serializable.
+        Keys: load_order, enabled, name, version, path, tags, supported_version, remote_file_id, picture,\
+            replace_path, replace_paths, replaces, dependencies, file, is_archive, root_dir, user_dir, short_description,\
+            game_custom_data, dup_id. Lists stay lists and unset optional fields are None.
+        """
+        data = {}
+        for f in fields(self):
+            if f.name.startswith("_"):
+                continue
+            value = getattr(self, f.name)
+            if isinstance(value, Path):
+                value = str(value)
+            elif isinstance(value, list):
+                value = [str(v) if isinstance(v, Path) else v for v in value]
+            elif isinstance(value, dict):
+                value = dict(value)
+            data[f.name] = value
+        data["dup_id"] = self._dup_id
+        return data
     
+    @classmethod
+    def from_dict(cls, data: dict) -> "Mod":
+        """Create a Mod from a dictionary written by `as_dict`, the inverse of it.
+        
+        Missing keys fall back to the field defaults, unknown keys are ignored.\
+            `load_order` and `enabled` are coerced from strings, e.g. "3" and "true".
+        """
+        init_fields = {f.name for f in fields(cls) if f.init and not f.name.startswith("_")}
+        kwargs = {k: v for k, v in data.items() if k in init_fields}
+        if "load_order" in kwargs:
+            kwargs["load_order"] = int(kwargs["load_order"])
+        if isinstance(kwargs.get("enabled"), str):
+            kwargs["enabled"] = kwargs["enabled"].strip().lower() in ("true", "1", "yes")
+        elif "enabled" in kwargs:
+            kwargs["enabled"] = bool(kwargs["enabled"])
+        mod = cls(**kwargs)
+        mod._dup_id = int(data.get("dup_id", 0))
+        return mod
     def load_from_descriptor(self, path: str|Path):
         """Load mod info from a descriptor file.
         
-        Note: This method requires importing get_mod_info, which should
-        be done locally to avoid circular imports.
+        The file is parsed with `parse_descriptor`. If that fails, falls back to `mod_loader.get_mod_info`,\
+            which is imported locally to avoid circular imports.
         """
-        # Import here to avoid circular dependency
-        from .mod_loader import get_mod_info
-        
-        _data = get_mod_info(Path(path))
+        try:
+            _data = parse_descriptor(path)
+        except (ValueError, UnicodeDecodeError) as e:
+            logger.warning("Falling back to regex parsing of %s: %s", path, str(e))
+            # Import here to avoid circular dependency
+            from .mod_loader import get_mod_info
+            _data = get_mod_info(Path(path))
         for k, v in _data.items():
             if hasattr(self, k):
                 setattr(self, k, v)
+        if _data.get("archive") and not _data.get("path"): # legacy descriptors: archive = "path/to.zip"
+            self.path = _data["archive"]
+            self.is_archive = True
         self.path = Path(self.path) # ensure Path object
         self.file = Path(path)
         if self.path.parts[0] == "mod": # adjust relative path
             self.path = Path(CK3_DOC_DIR)/self.path
             self.save_to_descriptor(path) # save adjusted path back to descriptor
+    @staticmethod
+    def game_path(path: str|Path) -> str:
+        """Formats a path with forward slashes as the CK3 launcher expects, regardless of the host OS.
+        
+        `Path.as_posix` keeps backslashes of Windows style paths on POSIX hosts, so they're replaced explicitly.
+        """
+        return str(path).replace("\\", "/")
     def save_to_descriptor(self, path: str|Path):
         """Save mod info to a descriptor file.
         
@@ -94,36 +233,79 @@ class Mod:
         lines = []
         lines.append(f'name = "{self.name}"')
         lines.append(f'version = "{self.version}"')
-        lines.append(f'path = "{self.path.as_posix()}"')
+        path_key = "archive" if self.is_archive else "path"
+        lines.append(f'{path_key} = "{self.game_path(self.path)}"')
         if self.tags:
             tags_str = '", "'.join(self.tags)
             lines.append(f'tags={{"{tags_str}"}}')
         if self.supported_version is not None:
-            lines.append(f'supported_version = {self.supported_version}')
+            lines.append(f'supported_version = "{self.supported_version}"')
         if self.remote_file_id:
             lines.append(f'remote_file_id = "{self.remote_file_id}"')
         if self.picture is not None and self.picture.parts:
-            lines.append(f'picture = "{self.picture.as_posix()}"')
-        if self.replace_path is not None and self.replace_path.parts:
-            lines.append(f'replace_path = "{self.replace_path.as_posix()}"')
+            lines.append(f'picture = "{self.game_path(self.picture)}"')
+        replace_paths = list(self.replace_paths)
+        if self.replace_path is not None and self.replace_path.parts and self.replace_path not in replace_paths:
+            replace_paths.insert(0, self.replace_path)
+        for replace_path in replace_paths:
+            lines.append(f'replace_path = "{self.game_path(replace_path)}"')
         if self.replaces:
             replaces_str = '", "'.join(self.replaces)
             lines.append(f'replaces = {{"{replaces_str}"}}')
         if self.dependencies:
             dependencies_str = '", "'.join(self.dependencies)
             lines.append(f'dependencies = {{"{dependencies_str}"}}')
+        if self.user_dir:
+            lines.append(f'user_dir = "{self.game_path(self.user_dir)}"')
+        if self.short_description:
+            lines.append(f'short_description = "{self.short_description}"')
+        if self.game_custom_data:
+            custom_data_str = " ".join(f"{k}={v}" for k, v in self.game_custom_data.items())
+            lines.append(f'game_custom_data = {{ {custom_data_str} }}')
         
         content = "\n".join(lines)
         with open(path, "w", encoding="utf-8") as f:
             f.write(content)
+    def verify_checksums(self, manifest_path: str|Path) -> List[Path]:
+        """Check the mod files against a manifest of `relative/path = <sha256>` lines.
+        
+        Returns:
+            List of files (relative to the mod path) that are missing or whose hash doesn't match.
+        """
+        mismatched = []
+        with open(manifest_path, "r", encoding="utf-8-sig") as f:
+            for line in f:
+                line = line.strip()
+                if not line or line.startswith("#") or "=" not in line:
+                    continue
+                rel_path, expected = (part.strip().strip('"') for part in line.rsplit("=", 1))
+                file = self.path/rel_path
+                if not file.is_file() or hashlib.sha256(file.read_bytes()).hexdigest() != expected.lower():
+                    mismatched.append(Path(rel_path))
+        return mismatched
+    def content_fingerprint(self) -> int:
+        """Returns a 64-bit hash of the relative paths and contents of all files in the mod, descriptors excluded."""
+        if not self.path.parts or not self.path.is_dir():
+            return 0
+        digest = hashlib.blake2b(digest_size=8)
+        for file in sorted(self.path.rglob("*")):
+            if not file.is_file() or file.suffix.lower() == ".mod":
+                continue
+            digest.update(file.relative_to(self.path).as_posix().encode("utf-8") + b"\0")
+            digest.update(file.read_bytes())
+        return int.from_bytes(digest.digest(), "big")
     def is_outdated(self, current_version: str) -> bool:
         """Check if the mod is outdated compared to the current game version.
         
         version format: "1.5.2", "1.6.*", "1.7.*.*" etc.
+        A `*` matches any value at its position and all following ones, so "1.12.*" is current for any "1.12.y".\
+            Missing components are treated the same way, e.g. "1.12" is current for "1.12.5".
         """
         if self.supported_version is None:
             return False
         for part0, part1 in zip(self.supported_version.strip().split("."), current_version.split(".")):
+            if part0 == "*" or part1 == "*":
+                return False
             try:
                 num0 = int(part0)
                 num1 = int(part1)
@@ -135,6 +317,29 @@ class Mod:
             elif num0 > num1:
                 return False
         return False  # Versions are equal up to the length of the shorter one
+    def outdated_severity(self, current_version: str) -> str:
+        """Classify how far `supported_version` is behind `current_version`.
+        
+        Returns:
+            "major", "minor" or "patch" for the first version component that is older, else "ok".\
+                An unset `supported_version` is "ok", `*` matches as in `is_outdated`.
+        """
+        if self.supported_version is None:
+            return "ok"
+        severities = ("major", "minor")
+        for i, (part0, part1) in enumerate(zip(self.supported_version.strip().split("."), current_version.split("."))):
+            if part0 == "*" or part1 == "*":
+                return "ok"
+            try:
+                num0 = int(part0)
+                num1 = int(part1)
+            except ValueError:
+                return "ok"
+            if num0 < num1:
+                return severities[i] if i < len(severities) else "patch"
+            elif num0 > num1:
+                return "ok"
+        return "ok"
     
     # def __getstate__(self):
     #     state = self.__dict__.copy()
@@ -150,4 +355,150 @@ class Mod:
     #         object.__setattr__(self, "_duplicates", set())
 
     def __hash__(self):
-        return hash((self.name, self.path))
\ No newline at end of file
+        return hash((self.name, self.path))
+
+def classify_outdated(mods: List[Mod], current_version: str) -> List[tuple[str, str]]:
+    """Returns (mod name, severity) for each mod, see `Mod.outdated_severity`."""
+    return [(mod.dup_name, mod.outdated_severity(current_version)) for mod in mods]
+
+def _levenshtein(a: str, b: str, transpositions: bool = False) -> int:
+    """Edit distance of two strings, with `transpositions` swapping adjacent characters costs 1 instead of 2."""
+    before, previous = None, list(range(len(b) + 1))
+    for i, char_a in enumerate(a, 1):
+        current = [i]
+        for j, char_b in enumerate(b, 1):
+            current.append(min(previous[j] + 1, current[j-1] + 1, previous[j-1] + (char_a != char_b)))
+            if transpositions and before is not None and j > 1 and char_a == b[j-2] and a[i-2] == char_b:
+                current[j] = min(current[j], before[j-2] + 1)
+        before, previous = previous, current
+    return previous[-1]
+
+def find_near_duplicates(mods: List[Mod], threshold: float = 0.85) -> List[tuple[str, str, float]]:
+    """Returns (mod name, mod name, similarity) for mods with similar names, e.g. the same mod re-uploaded.
+    
+    Names are compared lowercased with punctuation and whitespace collapsed, similarity is\
+        1 - Levenshtein distance / longer name length. Pairs at or above `threshold` are returned, most similar first.
+    """
+    normalized = [(mod.dup_name, " ".join(re.findall(r"\w+", mod.name.lower()))) for mod in mods]
+    pairs = []
+    for i, (name_a, norm_a) in enumerate(normalized):
+        for name_b, norm_b in normalized[i+1:]:
+            longest = max(len(norm_a), len(norm_b))
+            if not longest:
+                continue
+            similarity = 1 - _levenshtein(norm_a, norm_b) / longest
+            if similarity >= threshold:
+                pairs.append((name_a, name_b, similarity))
+    pairs.sort(key=lambda pair: -pair[2])
+    return pairs
+
+def resolve_load_order(mods: List[Mod]) -> List[Mod]:
+    """Sorts mods so each one comes after the mods named in its `dependencies`, reassigning `load_order` to 0..n.
+    
+    The sort is stable, independent mods keep their current `load_order`. Dependencies missing from `mods`\
+        and mods depending on themselves are ignored.
+    Raises:
+        ValueError: If the dependencies form a cycle, naming the mods in it.
+    """
+    index = {}
+    for i, mod in enumerate(mods):
+        index.setdefault(mod.name, i)
+    dependents: list[list[int]] = [[] for _ in mods]
+    in_degree = [0] * len(mods)
+    for i, mod in enumerate(mods):
+        for dependency in set(mod.dependencies):
+            j = index.get(dependency)
+            if j is not None and j != i:
+                dependents[j].append(i)
+                in_degree[i] += 1
+    ready = [(mod.load_order, i) for i, mod in enumerate(mods) if not in_degree[i]]
+    heapq.heapify(ready)
+    ordered = []
+    while ready:
+        _, i = heapq.heappop(ready)
+        ordered.append(i)
+        for j in dependents[i]:
+            in_degree[j] -= 1
+            if not in_degree[j]:
+                heapq.heappush(ready, (mods[j].load_order, j))
+    if len(ordered) < len(mods):
+        # drop the mods that are only blocked by a cycle, what's left depends on each other
+        remaining = set(range(len(mods))) - set(ordered)
+        pruned = True
+        while pruned:
+            pruned = False
+            for i in list(remaining):
+                if not any(j in remaining for j in dependents[i]):
+                    remaining.discard(i)
+                    pruned = True
+        names = ", ".join(mods[i].dup_name for i in sorted(remaining))
+        raise ValueError(f"Dependency cycle among mods: {names}")
+    result = [mods[i] for i in ordered]
+    for load_order, mod in enumerate(result):
+        mod.load_order = load_order
+    return result
+
+def find_dependency_cycles(mods: List[Mod]) -> List[List[str]]:
+    """Returns the groups of mods that depend on each other, directly or through other mods.
+    
+    Each group is a strongly connected component of the `dependencies` graph with more than one mod,\
+        or a single mod depending on itself, with names in traversal order.
+    """
+    names = {mod.name for mod in mods}
+    graph: dict[str, list[str]] = {}
+    for mod in mods:
+        graph.setdefault(mod.name, []).extend(dep for dep in mod.dependencies if dep in names)
+    order: dict[str, int] = {}
+    low: dict[str, int] = {}
+    stack: list[str] = []
+    on_stack: set[str] = set()
+    cycles = []
+    
+    def visit(root: str) -> None: # Tarjan's algorithm, iterative so long dependency chains don't hit the recursion limit
+        work = [(root, iter(graph[root]))]
+        order[root] = low[root] = len(order)
+        stack.append(root)
+        on_stack.add(root)
+        while work:
+            name, deps = work[-1]
+            for dep in deps:
+                if dep not in order:
+                    order[dep] = low[dep] = len(order)
+                    stack.append(dep)
+                    on_stack.add(dep)
+                    work.append((dep, iter(graph[dep])))
+                    break
+                elif dep in on_stack:
+                    low[name] = min(low[name], order[dep])
+            else: # all dependencies visited
+                work.pop()
+                if work:
+                    parent = work[-1][0]
+                    low[parent] = min(low[parent], low[name])
+                if low[name] == order[name]:
+                    component = []
+                    while True:
+                        member = stack.pop()
+                        on_stack.discard(member)
+                        component.append(member)
+                        if member == name:
+                            break
+                    if len(component) > 1 or name in graph[name]:
+                        cycles.append(sorted(component, key=order.__getitem__))
+    
+    for name in graph:
+        if name not in order:
+            visit(name)
+    return cycles
+
+def assign_dup_ids(mods: List[Mod]) -> List[Mod]:
+    """Numbers mods sharing a name so `dup_name` tells them apart, e.g. two workshop items both named "Name".
+    
+    Per name, the first mod by `load_order` keeps `dup_id` 0 ("Name"), the rest get 1, 2, ... ("Name#1", "Name#2").\
+        Unlike `ModList.add_duplicate`, the numbering doesn't depend on the insertion order.
+    """
+    seen: dict[str, int] = {}
+    for mod in sorted(mods, key=lambda mod: mod.load_order):
+        mod._dup_id = seen.get(mod.name, 0)
+        seen[mod.name] = mod._dup_id + 1
+    return mods
\ No newline at end of file
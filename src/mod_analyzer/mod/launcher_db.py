@@ -0,0 +1,84 @@
+"""
+Access to the playsets of the official launcher, stored in launcher-v2.sqlite.
+Kept out of the package exports, import `mod_analyzer.mod.launcher_db` to use it.
+"""
+import sqlite3
+import uuid
+from datetime import datetime, timezone
+from pathlib import Path
+
+from .mod_loader import CK3_DOC_DIR
+
+LAUNCHER_DB_PATH = CK3_DOC_DIR/"launcher-v2.sqlite"
+
+def read_playsets(db_path: str|Path = LAUNCHER_DB_PATH) -> list[tuple[str, bool, list[tuple[str, int, bool]]]]:
+    """Reads the launcher playsets.
+
+    Returns:
+        List of (playset name, is active, [(mod name, position, enabled)]), mods sorted by position.
+    """
+    conn = sqlite3.connect(f"file:{Path(db_path).as_posix()}?mode=ro", uri=True)
+    try:
+        playsets = conn.execute("SELECT id, name, isActive FROM playsets ORDER BY name").fetchall()
+        result = []
+        for playset_id, name, is_active in playsets:
+            rows = conn.execute(
+                "SELECT mods.displayName, playsets_mods.position, playsets_mods.enabled "
+                "FROM playsets_mods JOIN mods ON mods.id = playsets_mods.modId "
+                "WHERE playsets_mods.playsetId = ? ORDER BY playsets_mods.position",
+                (playset_id,),
+            ).fetchall()
+            mods = [(mod_name, int(position or 0), bool(enabled)) for mod_name, position, enabled in rows]
+            result.append((name, bool(is_active), mods))
+    finally:
+        conn.close()
+    return result
+
+def write_playset(db_path: str|Path, name: str, entries: list[tuple[str, int, bool]]) -> None:
+    """Creates or replaces the playset `name` with the given (mod name, position, enabled) entries.
+
+    All changes are made in one transaction, on failure the database is left untouched and an OSError is raised.
+    The database must exist, a wrong path raises instead of creating an empty one.
+    """
+    try:
+        conn = sqlite3.connect(f"file:{Path(db_path).as_posix()}?mode=rw", uri=True)
+    except sqlite3.Error as e:
+        raise OSError(f"Failed to open launcher database {db_path}: {e}") from e
+    try:
+        with conn: # commits, or rolls back on error
+            columns = {row[1] for row in conn.execute("PRAGMA table_info(playsets)")}
+            now = _launcher_timestamp()
+            row = conn.execute("SELECT id FROM playsets WHERE name = ?", (name,)).fetchone()
+            if row is not None:
+                playset_id = row[0]
+                conn.execute("DELETE FROM playsets_mods WHERE playsetId = ?", (playset_id,))
+                if "updatedOn" in columns:
+                    conn.execute("UPDATE playsets SET updatedOn = ? WHERE id = ?", (now, playset_id))
+            else:
+                playset_id = str(uuid.uuid4())
+                # the launcher declares these NOT NULL, older schemas may lack some of them
+                values = {
+                    "id": playset_id, "name": name, "isActive": 0, "loadOrder": "custom", "createdOn": now,
+                    "isRemoved": 0, "hasNotApprovedChanges": 0,
+                }
+                values = {column: value for column, value in values.items() if column in columns}
+                conn.execute(
+                    f"INSERT INTO playsets ({', '.join(values)}) VALUES ({', '.join('?' for _ in values)})",
+                    tuple(values.values()),
+                )
+            for mod_name, position, enabled in entries:
+                mod_row = conn.execute("SELECT id FROM mods WHERE displayName = ?", (mod_name,)).fetchone()
+                if mod_row is None:
+                    raise sqlite3.IntegrityError(f"mod not registered in the launcher: {mod_name}")
+                conn.execute(
+                    "INSERT INTO playsets_mods (playsetId, modId, position, enabled) VALUES (?, ?, ?, ?)",
+                    (playset_id, mod_row[0], position, int(enabled)),
+                )
+    except sqlite3.Error as e:
+        raise OSError(f"Failed to write playset '{name}': {e}") from e
+    finally:
+        conn.close()
+
+def _launcher_timestamp() -> str:
+    """Returns the current time in the launcher's format, e.g. `2024-01-01 12:00:00.000 +00:00`."""
+    return datetime.now(timezone.utc).strftime("%Y-%m-%d %H:%M:%S.%f")[:-3] + " +00:00"
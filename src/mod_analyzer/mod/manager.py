@@ -1,20 +1,72 @@
 
 import os
+import csv
 import json
-from typing import Optional, Iterable
+import pickle
+import hashlib
+from typing import Optional, Iterable, Callable
+from dataclasses import dataclass
 from pathlib import Path
-from concurrent.futures import as_completed
+from concurrent.futures import FIRST_COMPLETED, ProcessPoolExecutor, wait
+from functools import partial
+from difflib import SequenceMatcher
 import time
+import threading
 import logging
 pkg = (__package__ or __name__).split('.')[0]
 logger = logging.getLogger(pkg)
 
-from utils.cocurrent import run_multithread, run_multiprocess
-from ..encoding import detect_encoding
+from utils.cocurrent import run_multithread
+from ..encoding import detect_encoding, detect_line_format, find_encoding_issues
 from . import paradox_parser, paradox_loc_parser
 from . import Mod, DefinitionNode, DefinitionDirectoryNode, DefinitionFileNode, ModList, SourceList, SourceEntry
+from .mod_list import DefinitionValueNode, DefinitionVirtualNode
 from .mod_loader import get_mod_info, get_enabled_mod_descriptors, get_all_mod_descriptors, get_all_mod_descriptor_paths, get_playset_mod_descriptors, get_enabled_mod_dirs, load_mod_descriptor
 from .conflict import non_conflict_keywords
+from .descriptor import _levenshtein
+
+# content directories whose definitions take `$PARAM$` arguments
+SCRIPTED_DIRS = ("common/scripted_effects", "common/scripted_triggers", "common/scripted_guis")
+# loc keys the game looks up from an identifier name without a script reference, e.g. `trait_brave`, `brave_desc`
+LOC_AUTO_PREFIXES = ("trait_", "building_", "culture_", "faith_", "religion_")
+LOC_AUTO_SUFFIXES = ("_desc", "_name", "_adj", "_flavor", "_tooltip", "_effect", "_short")
+# top level directories the game reads content from
+CONTENT_DIRS = frozenset({
+    "common", "content_source", "data_binding", "events", "fonts", "gfx", "gui",
+    "history", "localization", "map_data", "music", "notifications", "sound", "tests",
+})
+PARSE_CACHE_VERSION = 3 # bump when the node classes change, entries of other versions are ignored
+
+class ExtractionCancelled(RuntimeError):
+    """Raised by `ModManager.build_file_tree` when its `cancel` event is set."""
+
+@dataclass
+class ModManagerConfig:
+    """Bundles the options of `ModManager`, see `ModManager.build_file_tree` for details.
+    
+    Example:
+    ```
+        config = ModManagerConfig(conflict_check_range="enabled", process_max_workers=8)
+        manager = ModManager.with_config(config)
+        manager.build_mod_list()
+        manager.build()
+    ```
+    """
+    file_range: Optional[str] = None
+    conflict_check_range: Optional[str] = None
+    process_max_workers: Optional[int] = None
+    include_disabled: bool = False
+    language: str|list[str] = "english" # several languages are parsed into one `<loc>` node each, the first is the default
+    file_tree_depth: Optional[int] = None
+    max_def_depth: int = 0
+    content_dirs_only: bool = False
+    check_script_conflicts: bool = True
+    check_loc_conflicts: bool = True
+    detect_intra_mod_dupes: bool = False
+    lazy_values: bool = False
+    loc_comments: bool = False
+    node_budget: int = -1
+    cache_dir: Optional[str|Path] = None # parsed files are cached on disk if set
 
 class ModManager:
     """Checks for conflicts in mod definitions across multiple mods.    
@@ -39,12 +91,63 @@ class ModManager:
     WORKSHOP_DIR = r"C:\Program Files (x86)\Steam\steamapps\workshop\content\1158310"
     root_dir: Path
     mod_list: ModList[str]
-    _max_def_depth: int = 0
+    # parse options, passed to `_extract_file_definitions` explicitly so they reach the parsing processes
+    max_def_depth: int = 0
+    lazy_values: bool = False # scalar values are read back from the files on access, saves memory for large mod lists
+    loc_comments: bool = False # attach the comment line above a loc key to its node
+    node_budget: int = -1 # max tree-sitter nodes visited per script file, protects against pathological files
+    cache_dir: Optional[Path] = None # parsed definitions are cached here, keyed by file path, mtime and size
     language: str = "english" # default language for localization parsing
-    def __init__(self):
+    languages: list[str] # additional languages to parse, each gets its own `<loc:language>` node, set per instance
+    file_tree_depth: Optional[int] = None # max directory levels in define_table, deeper paths are kept as a single key
+    content_dirs_only: bool = False # only parse txt files under `CONTENT_DIRS`, others (e.g. docs/changelog.txt) are kept as "other"
+    # conflict recording per definition kind, sources are merged either way so `recompute_conflicts` applies toggles
+    check_script_conflicts: bool = True
+    check_loc_conflicts: bool = True
+    detect_intra_mod_dupes: bool = False # record loc keys a mod defines more than once, see `get_duplicate_loc_keys`
+    def __init__(self, cache_dir: Optional[str|Path] = None):
         self.mod_list = ModList()
+        self.config = ModManagerConfig()
+        self.languages = []
+        self.priority_override: dict[str, int] = {} # mod name -> priority used instead of its load order to pick conflict winners
+        if cache_dir is not None:
+            self.config.cache_dir = cache_dir
+            self.cache_dir = Path(cache_dir)
         self.reset()
         
+    @classmethod
+    def with_config(cls, config: ModManagerConfig) -> "ModManager":
+        """Creates a ModManager configured once from a `ModManagerConfig`."""
+        manager = cls()
+        manager.apply_config(config)
+        return manager
+    
+    def apply_config(self, config: ModManagerConfig) -> None:
+        self.config = config
+        languages = [config.language] if isinstance(config.language, str) else list(config.language)
+        self.language, self.languages = languages[0], languages[1:]
+        self.file_tree_depth = config.file_tree_depth
+        self.content_dirs_only = config.content_dirs_only
+        self.check_script_conflicts = config.check_script_conflicts
+        self.check_loc_conflicts = config.check_loc_conflicts
+        self.detect_intra_mod_dupes = config.detect_intra_mod_dupes
+        self.max_def_depth = config.max_def_depth
+        self.lazy_values = config.lazy_values
+        self.loc_comments = config.loc_comments
+        self.node_budget = config.node_budget
+        self.cache_dir = Path(config.cache_dir) if config.cache_dir is not None else None
+        
+    def build(self, progress: Optional[Callable[[int, int, str], None]] = None, cancel: Optional[threading.Event] = None) -> None:
+        """Builds the file tree with the options of `self.config`, see `build_file_tree` for `progress` and `cancel`."""
+        self.build_file_tree(
+            file_range=self.config.file_range,
+            conflict_check_range=self.config.conflict_check_range,
+            process_max_workers=self.config.process_max_workers,
+            include_disabled=self.config.include_disabled,
+            progress=progress,
+            cancel=cancel,
+        )
+        
     def reset(self):
         self.definitions: dict[str, list[DefinitionNode]] = {}
         self.define_table = DefinitionDirectoryNode(r"%root%", "./")
@@ -53,6 +156,15 @@ class ModManager:
         self.conflict_identifiers = []
         self.conflict_mods: set[str] = set()
         self.conflict_check_range: Optional[str] = None # "all", "enabled", "disabled", None
+        self.identifier_index: dict[str, DefinitionNode] = {} # content dir (posix) -> its "<def>"/"<loc>" node
+        self.parsed_files: dict[str, list[DefinitionNode]] = {} # mod name -> parsed file nodes contributed by the mod
+        self.identical_definitions: set[tuple[str,str]] = set() # (rel_dir, identifier) defined identically by all sources
+        self.built_mods: list[str] = [] # names of the mods included in the last file tree build
+        self.extraction_stats: dict = {} # see `last_extraction_stats`
+        self.failed_files: list[tuple[Path, str]] = [] # (file, error) of files that couldn't be read or parsed
+        self.duplicate_loc_keys: list[tuple[str, Path, int]] = [] # (key, file, line) of loc keys redefined by the same mod
+        self._merge_order: list[tuple[SourceEntry, DefinitionNode]] = [] # parsed files in the order they were merged, see `_remerge`
+        self._cancel: Optional[threading.Event] = None # cancel event of the running build, see `build_file_tree`
     @property
     def load_order(self) -> list[str]:
         """Returns the current load order of mods as a list of mod IDs."""
@@ -67,30 +179,114 @@ class ModManager:
                 logger.warning("Mod: \"%s\" not found in mod list.", mod_id)
         self.mod_list.sort()
     
+    def _parse_options(self) -> dict:
+        """Returns the keyword arguments of `_extract_file_definitions` for this manager."""
+        return {
+            "max_def_depth": self.max_def_depth,
+            "lazy_values": self.lazy_values,
+            "loc_comments": self.loc_comments,
+            "node_budget": self.node_budget,
+            "cache_dir": self.cache_dir,
+        }
+
     @staticmethod
-    def _extract_file_definitions(file_entry:SourceEntry) -> tuple[SourceEntry, Optional[DefinitionNode], Optional[str]]:
+    def _extract_file_definitions(file_entry:SourceEntry, max_def_depth: int = 0, lazy_values: bool = False, loc_comments: bool = False, node_budget: int = -1, cache_dir: Optional[Path] = None) -> tuple[SourceEntry, Optional[DefinitionNode], Optional[str]]:
         """Parses a single file entry. Helps with multiprocessing."""
         # For Developers: Keep this function at staticmethod level (or module level) to be picklable by ProcessPoolExecutor!!!
+        # Options are passed as arguments, spawned worker processes don't see values set on the class or instance at runtime.
         try:
+            options = (max_def_depth, lazy_values, loc_comments, node_budget)
+            cache_file = ModManager._cache_file(file_entry, cache_dir, options)
+            if cache_file is not None and (cached := ModManager._load_cached(cache_file, file_entry)) is not None:
+                return (file_entry, cached, None)
             encoding = detect_encoding(file_entry.file)
+            source = file_entry.file.read_bytes()
+            file_node = DefinitionFileNode(file_entry.file.name, str(file_entry.rel_path.parent), source=file_entry)
+            file_node.line_ending, file_node.indent_unit = detect_line_format(source)
+            file_node.encoding_issues = find_encoding_issues(source, file_entry.file.suffix)
             if file_entry.file.suffix.lower() == ".txt":
-                source=file_entry.file.read_bytes()
                 tree = paradox_parser.parser.parse(source)
+                file_node.syntax_error = tree.root_node.has_error
+                if file_node.syntax_error:
+                    file_node.parse_errors = paradox_parser.error_ranges(tree.root_node)
                 definitions: DefinitionNode = paradox_parser.extract_node_definitions(
                     tree.root_node, 
-                    DefinitionNode(file_entry.file.name, str(file_entry.rel_path.parent), source=file_entry),
-                    max_depth=ModManager._max_def_depth
+                    file_node,
+                    max_depth=max_def_depth,
+                    lazy_values=lazy_values,
+                    node_budget=node_budget,
                 )
+                if file_node.budget_exceeded:
+                    logger.warning("Parsing budget exceeded in %s, keeping partial definitions", file_entry.file)
             elif file_entry.file.suffix.lower() == ".yml":
                 definitions: DefinitionNode = paradox_loc_parser.extract_definitions(
-                    file_entry.file.read_text(encoding=encoding), 
-                    DefinitionNode(file_entry.file.name, str(file_entry.rel_path.parent), source=file_entry),
+                    source.decode(encoding).replace('\r\n', '\n'), 
+                    file_node,
+                    attach_comments=loc_comments,
                 )
         except Exception as e:
             logger.exception(f"Error reading %s: %s", file_entry.file, str(e))
             return (file_entry, None, str(e))
+        if cache_file is not None:
+            try:
+                ModManager._save_cached(cache_file, definitions)
+            except (OSError, pickle.PicklingError) as e: # a failed cache write must not drop the parsed file
+                logger.debug("Couldn't write parse cache %s: %s", cache_file, str(e))
         return (file_entry, definitions, None)
     
+    def _check_cancelled(self) -> None:
+        if self._cancel is not None and self._cancel.is_set():
+            raise ExtractionCancelled("cancelled")
+
+    @staticmethod
+    def _cache_file(file_entry: SourceEntry, cache_dir: Optional[Path], options: tuple) -> Optional[Path]:
+        """Returns the parse cache file of a source file parsed with `options`, None if caching is disabled."""
+        if cache_dir is None:
+            return None
+        stat = file_entry.file.stat()
+        key = f"{file_entry.file.resolve()}|{stat.st_mtime_ns}|{stat.st_size}|{options}"
+        return Path(cache_dir)/f"{hashlib.sha1(key.encode('utf-8')).hexdigest()}.pkl"
+
+    @staticmethod
+    def _load_cached(cache_file: Path, file_entry: SourceEntry) -> Optional[DefinitionNode]:
+        """Loads cached definitions and links them to `file_entry`, None if missing, stale or unreadable."""
+        try:
+            with open(cache_file, "rb") as f:
+                version, definitions = pickle.load(f)
+        except FileNotFoundError:
+            return None
+        except Exception as e:
+            logger.debug("Ignoring unreadable parse cache %s: %s", cache_file, str(e))
+            return None
+        if version != PARSE_CACHE_VERSION:
+            return None
+        nodes = [definitions]
+        while nodes:
+            node = nodes.pop()
+            for name in list(node.sources.keys()):
+                node.sources[name] = file_entry
+            nodes.extend(node.values())
+        definitions.from_cache = True
+        return definitions
+
+    @staticmethod
+    def _save_cached(cache_file: Path, definitions: DefinitionNode) -> None:
+        cache_file.parent.mkdir(parents=True, exist_ok=True)
+        tmp_file = cache_file.with_suffix(f".{os.getpid()}.tmp") # workers may write the same entry
+        with open(tmp_file, "wb") as f:
+            pickle.dump((PARSE_CACHE_VERSION, definitions.to_standalone_tree()), f, protocol=pickle.HIGHEST_PROTOCOL)
+        os.replace(tmp_file, cache_file)
+
+    def clear_cache(self) -> int:
+        """Deletes the parse cache entries in `cache_dir` and returns how many were removed."""
+        if self.cache_dir is None or not self.cache_dir.is_dir():
+            return 0
+        removed = 0
+        for cache_file in self.cache_dir.glob("*.pkl"):
+            cache_file.unlink(missing_ok=True)
+            removed += 1
+        return removed
+
     def save_profile(self, profile_path: str|Path):
         """Save the current mod list as a profile to file."""
         if profile_path == "<Default>": # save to dlc_load.json
@@ -208,13 +404,15 @@ class ModManager:
         if mode == "default": # update enabled status based on dlc_load.json
             self.mod_list.update(ModList(get_enabled_mod_descriptors(path)))
     
-    def build_file_tree(self, file_range:Optional[str]= None, conflict_check_range: Optional[str]=None, process_max_workers:Optional[int]= None):
+    def build_file_tree(self, file_range:Optional[str]= None, conflict_check_range: Optional[str]=None, process_max_workers:Optional[int]= None, include_disabled: bool = False, progress: Optional[Callable[[int, int, str], None]] = None, cancel: Optional[threading.Event] = None):
         """Builds a file tree representation of the mod structure.
         
         Args:
-            file_range (str, optional): Range of files to include. Defaults to "all".
+            file_range (str, optional): Range of files to include. Defaults to None.
                 Options:
-                    - None      : Include all files
+                    - None      : Include enabled files, and disabled ones if `include_disabled` is set or\
+                                  `conflict_check_range` is "all" or "disabled"
+                    - "all"     : Include all files
                     - "enabled" : Include only enabled files
                     - "disabled": Include only disabled files
             conflict_check_range (str, optional): Range of mods to check for conflicts. Defaults to None.
@@ -223,18 +421,43 @@ class ModManager:
                     - "all"     : Check all mods
                     - "enabled" : Check only enabled mods
                     - "disabled": Check only disabled mods
+            include_disabled (bool, optional): With the default `file_range`, collects disabled mods as well.\
+                Otherwise they are skipped entirely during collection. Defaults to False.
+            progress (callable, optional): Called as `progress(done, total, stage)` from the calling thread,\
+                after each mod ("collect") and each file ("other", "txt", "yml") is processed. Defaults to None.
+            cancel (threading.Event, optional): Stops the build with `ExtractionCancelled` once set. Cancellation is\
+                cooperative, it's only checked between mods, files and phases; with `process_max_workers` the files\
+                not yet started are dropped and the running ones finish first. The partially built tree is discarded\
+                with `reset`, so building again starts from a clean manager. Defaults to None.
         """
         self.conflict_check_range = conflict_check_range
-        if file_range == "enabled":
-            mod_list = ModList(self.mod_list.enabled)
+        if file_range is None:
+            include_disabled = include_disabled or conflict_check_range in ("all", "disabled")
+        if file_range == "enabled" or (file_range is None and not include_disabled):
+            enabled_keys = [key for key, mod in self.mod_list.items() if mod.enabled]
+            mod_list = ModList({key: self.mod_list[key] for key in enabled_keys}, load_order=enabled_keys)
         elif file_range == "disabled":
             mod_list = ModList(self.mod_list.disabled)
         else:
             mod_list = self.mod_list
         # self._build_file_tree(mod_list)
         t0 = time.perf_counter()
-        self._build_file_tree(mod_list, process_max_workers)
+        self._cancel = cancel
+        try:
+            self._build_file_tree(mod_list, process_max_workers, progress)
+        except ExtractionCancelled:
+            self.reset() # a partial tree would be merged into the next build
+            raise
+        finally:
+            self._cancel = None
         logger.info("Done building file tree in %.2f seconds", time.perf_counter()-t0)
+        stats = self.extraction_stats
+        logger.info(
+            "Extraction summary: %d files (%d parsed), %d nodes, %d conflicts; collect %.2fs, other %.2fs, extract %.2fs",
+            stats["files"], stats["parsed_files"], stats["nodes"], stats["conflicts"], *stats["phase_seconds"].values()
+        )
+        if truncated := self.truncated_files():
+            logger.debug("%d files have definitions deeper than max depth %d", len(truncated), self.max_def_depth)
         
     def _get_mod_file_entries(self, mod_info:Mod) -> dict[str, list[SourceEntry]]:
         """Gets the file entries for a given mod."""
@@ -254,74 +477,320 @@ class ModManager:
                 # Create SourceEntry for tracking
                 file_entry = SourceEntry(dirpath/file)
                 file_entry.link_mod(mod_info)                 
-                if file.lower().endswith(".txt"):
+                if file.lower().endswith(".txt") and (not self.content_dirs_only or relpath.parts[0] in CONTENT_DIRS):
                     file_entries["txt"].append(file_entry)
                 elif (file.lower().endswith(".yml") and 
-                      file.endswith(f'l_{self.language}.yml') # only parse localization for the specified language
+                      any(file.endswith(f'l_{language}.yml') for language in self.parsed_languages()) # only parse the specified languages
                 ):
                     file_entries["yml"].append(file_entry)
-                elif file.lower().endswith((".yml",".gui", ".csv", ".dds")):
+                elif file.lower().endswith((".txt", ".yml",".gui", ".csv", ".dds")):
                 # These files are not parsed for definitions, but added to file tree
                 # TODO: gui files can be parsed for definitions later
                     file_entries["other"].append(file_entry)
         return file_entries
     
-    def _extract_definitions(self, file_entries:Iterable[SourceEntry]) -> None:
+    def _extract_definitions(self, file_entries:list[SourceEntry], progress: Optional[Callable[[int, int, str], None]] = None, stage: str = "txt") -> None:
         '''
         Uses Paradox Tree Sitter Parser to extract definitions.
         '''
-        for file_entry in file_entries:
-            _, definitions, e = self._extract_file_definitions(file_entry)
+        for done, file_entry in enumerate(file_entries, 1):
+            self._check_cancelled()
+            _, definitions, e = self._extract_file_definitions(file_entry, **self._parse_options())
+            if progress is not None:
+                progress(done, len(file_entries), stage)
             if definitions is None:
                 logger.error("Error parsing %s: %s", file_entry.file, str(e))
+                self.failed_files.append((file_entry.file, str(e)))
                 continue
             has_conflict = self.add_definition(file_entry, definitions)
         for obj in self.conflict_identifiers:
             self.conflict_issues[(obj.rel_dir.as_posix(),obj.name)] = obj.sources
                     
+    def _setdefault_tree_node(self, rel_path: Path, default: DefinitionNode) -> DefinitionNode:
+        """Places a node in `define_table` at `rel_path`, honoring `file_tree_depth`.
+        
+        Beyond `file_tree_depth` directory levels the rest of the path is used as one key,\
+            e.g. depth 1: `common/traits/00_traits.txt` -> `common` / `traits/00_traits.txt`.
+        """
+        parts = Path(rel_path).parts
+        depth = self.file_tree_depth
+        if depth is None or len(parts) - 1 <= depth:
+            return self.define_table.setdefault_by_dir(rel_path, default)
+        capped = self.define_table.setdefault_by_dir(Path(*parts[:depth])) if depth > 0 else self.define_table
+        return capped.setdefault(Path(*parts[depth:]).as_posix(), default)
+        
+    def _get_tree_node(self, rel_path: str|Path) -> Optional[DefinitionNode]:
+        """Looks up the node at `rel_path` in `define_table`, honoring `file_tree_depth`."""
+        parts = Path(rel_path).parts
+        depth = self.file_tree_depth
+        if depth is None or len(parts) - 1 <= depth:
+            return self.define_table.get_by_dir(rel_path)
+        capped = self.define_table.get_by_dir(Path(*parts[:depth])) if depth > 0 else self.define_table
+        return capped.get(Path(*parts[depth:]).as_posix()) if capped is not None else None
+        
     def add_definition(self, file_entry:SourceEntry, definitions:DefinitionNode) -> bool:
-        _ = self.define_table.setdefault_by_dir(file_entry.rel_path, definitions)
+        _ = self._setdefault_tree_node(file_entry.rel_path, definitions)
         if file_entry.file.suffix.lower() =='.txt':            
-            def_node: DefinitionNode = self.define_table.setdefault_by_dir(
+            def_node: DefinitionNode = self._setdefault_tree_node(
                 # use "<def>" as a virtual space under the rel dir of the file, for tracking from root
                 file_entry.rel_path.parent/'<def>', 
-                DefinitionFileNode('<def>', file_entry.rel_path.parent)
+                DefinitionVirtualNode('<def>', file_entry.rel_path.parent)
             )
+            self.identifier_index[file_entry.rel_path.parent.as_posix()] = def_node
         elif file_entry.file.suffix.lower() =='.yml':
-            def_node: DefinitionNode = self.define_table.setdefault_by_dir(
+            loc_name = self._loc_node_name(file_entry.file.name)
+            def_node: DefinitionNode = self._setdefault_tree_node(
                 # use "<loc>" as a virtual space under the rel dir of the file, for tracking from root
-                'localization/<loc>', 
-                DefinitionFileNode('<loc>', file_entry.rel_path.parent)
+                Path('localization')/loc_name, 
+                DefinitionVirtualNode(loc_name, file_entry.rel_path.parent)
             )
+            self.identifier_index[self._loc_index_key(loc_name)] = def_node
+        self.parsed_files.setdefault(file_entry.name or "", []).append(definitions)
+        self._merge_order.append((file_entry, definitions))
+        if (replaced_by := self._replaced_by(file_entry)) is not None:
+            # the file stays in the file tree, but the game never loads its definitions
+            definitions.replaced_by = replaced_by.dup_name
+            return False
         has_conflict = False
-        if def_node == definitions: # no matching path found, safe to add without conflict
+        if def_node is definitions: # no matching path found, safe to add without conflict
             return False
+        if self.detect_intra_mod_dupes:
+            self.duplicate_loc_keys.extend((key, file_entry.file, line) for key, line in getattr(definitions, 'duplicate_keys', []))
         for key, value in definitions.items():
             has_conflict = False
             _key_node = def_node.get(key)
             if key in non_conflict_keywords:
                 continue            
+            prev_source = _key_node.sources.get(file_entry.name) if _key_node else None
+            if (prev_source is not None and file_entry.file.suffix.lower() == '.yml' and
+                self._is_loc_replace(prev_source.rel_path) and not self._is_loc_replace(file_entry.rel_path)
+            ):
+                continue # within a mod, loc files in a "replace" folder take priority
+            if (self.detect_intra_mod_dupes and prev_source is not None and file_entry.file.suffix.lower() == '.yml' and
+                not self._is_loc_replace(file_entry.rel_path) and not self._is_loc_replace(prev_source.rel_path)
+            ): # a "replace" folder overriding the mod's own keys is intended
+                self.duplicate_loc_keys.append((key, file_entry.file, getattr(value, 'line', None) or 0))
             # Ensure the new value has the source set correctly
             value.set_source(file_entry)
             def_node[key] = value # always overwrite for now # TODO: handle defs that won't confilct with same names.
             self.definitions.setdefault(key, []).append(value)
             if _key_node:
                 def_node[key].sources.update(_key_node.sources) # merge sources 
+                def_node[key].set_source(file_entry) # keep the overriding file as this mod's source
                 has_conflict = def_node[key].has_conflict() or has_conflict
-            if has_conflict and self.conflict_check_range:
+            if has_conflict and self.conflict_check_range and self._checks_conflicts_in(def_node):
                 self.conflict_identifiers.append(def_node[key])
         return has_conflict
-            
-    def _extract_definitions_multiprocess(self, file_entries:Iterable[SourceEntry], max_workers:Optional[int]= None):
+
+    def _remerge(self) -> None:
+        """Merges the parsed files again in their original order, without re-parsing.
+        
+        Needed when the files dropped by a `replace_path` change, e.g. a replacing mod is toggled or a priority overridden.\
+            The result matches a full re-extraction with the current enabled mods and priorities.
+        """
+        merge_order = self._merge_order
+        for def_node in self.identifier_index.values():
+            def_node.clear()
+        self.definitions = {}
+        self.parsed_files = {}
+        self.duplicate_loc_keys = []
+        self.conflict_identifiers = []
+        self._merge_order = []
+        for file_entry, file_node in merge_order:
+            file_node.replaced_by = None
+            for child in file_node.values():
+                child.parent = file_node
+                for name in [name for name in child.sources if name != file_entry.name]:
+                    del child.sources[name] # sources merged from other mods
+            self.add_definition(file_entry, file_node)
+        self.recompute_conflicts()
+
+    def get_duplicate_loc_keys(self) -> list[tuple[str, Path, int]]:
+        """Returns (key, file, line) of each loc key a mod defines again, within a file or across its files.
+        
+        Recorded during the build when `detect_intra_mod_dupes` is set, the later definition wins as usual.
+        """
+        return list(self.duplicate_loc_keys)
+
+    def parsed_languages(self) -> list[str]:
+        """Returns the localization languages that are parsed, `language` first."""
+        return [self.language, *(language for language in self.languages if language != self.language)]
+
+    def _loc_node_name(self, file_name: str) -> str:
+        """Returns the merged node of a loc file: "<loc>" for `language`, "<loc:french>" for other languages."""
+        for language in self.languages:
+            if language != self.language and file_name.endswith(f'l_{language}.yml'):
+                return f'<loc:{language}>'
+        return '<loc>'
+
+    @staticmethod
+    def _loc_index_key(loc_name: str) -> str:
+        """Returns the `identifier_index` key of a `<loc>` node, "localization" or e.g. "localization:french"."""
+        return 'localization' + loc_name[len('<loc'):-1]
+
+    @staticmethod
+    def _is_loc_replace(rel_path: Path) -> bool:
+        """Whether a loc file is in a `localization/replace/<language>` (or `<language>/replace`) folder."""
+        return 'replace' in rel_path.parts[:-1]
+
+    def truncated_files(self) -> list[Path]:
+        """Lists the files whose definitions were cut off by `max_def_depth` or the node budget."""
+        return [
+            file_node.source.file
+            for file_nodes in self.parsed_files.values() for file_node in file_nodes
+            if file_node.source is not None and self._is_truncated(file_node)
+        ]
+
+    @staticmethod
+    def _is_truncated(node: DefinitionNode) -> bool:
+        """Whether parsing dropped any part of the node's body."""
+        return getattr(node, 'truncated', False) or any(getattr(child, 'truncated', False) for child in node.walk())
+
+    def parse_failures(self) -> list[tuple[Path, str]]:
+        """Lists the files that failed to read or parse with the reason, including files parsed despite syntax errors."""
+        return self.failed_files + [
+            (file_node.source.file, "syntax error")
+            for file_nodes in self.parsed_files.values() for file_node in file_nodes
+            if getattr(file_node, 'syntax_error', False) and file_node.source is not None
+        ]
+
+    def get_parse_errors(self) -> list[tuple[Path, str]]:
+        """Lists the read/parse failures with the reason, syntax errors are listed one by one with their position."""
+        errors = list(self.failed_files)
+        for file_nodes in self.parsed_files.values():
+            for file_node in file_nodes:
+                if file_node.source is None:
+                    continue
+                for start_row, start_col, _, _ in getattr(file_node, 'parse_errors', []):
+                    errors.append((file_node.source.file, f"syntax error at line {start_row + 1}, column {start_col + 1}"))
+        return errors
+
+    def encoding_issues(self) -> list[tuple[Path, str]]:
+        """Lists the encoding problems found while reading the parsed files, e.g. (file, "missing UTF-8 BOM")."""
+        return [
+            (file_node.source.file, issue)
+            for file_nodes in self.parsed_files.values() for file_node in file_nodes
+            if file_node.source is not None
+            for issue in getattr(file_node, 'encoding_issues', [])
+        ]
+
+    def identifiers_in(self, dirpath: str|Path) -> list[DefinitionNode]:
+        """Returns all identifiers defined in a content directory, e.g. `common/traits`."""
+        def_node = self.identifier_index.get(Path(dirpath).as_posix())
+        return list(def_node.values()) if def_node is not None else []
+
+    def scripted_signatures(self, dirs: Iterable[str|Path] = SCRIPTED_DIRS) -> dict[str, set[str]]:
+        """Returns the `$PARAM$` placeholders used by each scripted definition, e.g. {"my_effect": {"TARGET"}}.
+        
+        Parameters are only found in parsed bodies, so this requires `max_def_depth` to reach them.
+        """
+        return {
+            node.name: node.parameters()
+            for dirpath in dirs
+            for node in self.identifiers_in(dirpath)
+        }
+
+    def _find_mod_definition(self, name: str, rel_dir: str|Path, identifier: str) -> tuple[Optional[DefinitionNode], Optional[DefinitionNode]]:
+        """Returns (file node, identifier node) of mod `name`'s own version of an identifier."""
+        rel_dir = Path(rel_dir)
+        for file_node in reversed(self.parsed_files.get(name, [])): # later files override, as in add_definition
+            if file_node.rel_dir == rel_dir and identifier in file_node:
+                return file_node, file_node[identifier]
+        return None, None
+
+    def get_mod_definition(self, name: str, rel_dir: str|Path, identifier: str) -> Optional[DefinitionNode]:
+        """Returns mod `name`'s own version of an identifier, as parsed from its file before merging."""
+        return self._find_mod_definition(name, rel_dir, identifier)[1]
+
+    def generate_compatch(self, conflict: str|Path) -> str:
+        """Generates a compatch skeleton for a conflict, e.g. `common/traits/brave`.
+        
+        The winner's definition is emitted as-is, the overridden definitions are appended as comments.
+        """
+        conflict = Path(conflict)
+        rel_dir, identifier = conflict.parent.as_posix(), conflict.name
+        sources = self.conflict_issues.get((rel_dir, identifier))
+        if sources is None:
+            raise KeyError(f"No conflict found for {conflict.as_posix()}")
+        winner = self._get_winner(sources)
+        winner_name = winner.name if winner else None
+        losers = [src.name for src in sources.by_load_order() if src.name != winner_name]
+        lines = [
+            f"# Compatch skeleton for {rel_dir}::{identifier}",
+            f"# Winner: {winner_name}",
+            f"# Overridden: {', '.join(map(str, losers))}",
+        ]
+        for name in [winner_name, *losers]:
+            node = self.get_mod_definition(name or "", rel_dir, identifier)
+            if node is None:
+                continue
+            script = f"{identifier} = {{\n{node.to_script(1)}\n}}" if len(node) else f"{identifier} = {{ }}"
+            if name == winner_name:
+                lines.append(script)
+            else:
+                lines.append(f"# --- {name} ---")
+                lines.extend("# " + line for line in script.splitlines())
+        return "\n".join(lines) + "\n"
+
+    def _iter_merged_nodes(self, node: Optional[DefinitionNode] = None) -> Iterable[DefinitionNode]:
+        """Yields the virtual `<def>`/`<loc>` nodes holding merged definitions."""
+        node = self.define_table if node is None else node
+        for key, child in node.items():
+            if Path(key).name in ('<def>', '<loc>') or Path(key).name.startswith('<loc:'):
+                yield child
+            elif isinstance(child, DefinitionDirectoryNode):
+                yield from self._iter_merged_nodes(child)
+
+    def _checks_conflicts_in(self, def_node: DefinitionNode) -> bool:
+        """Whether conflicts are recorded for a merged `<def>` (script) or `<loc>` (localization) node."""
+        return self.check_loc_conflicts if def_node.name.startswith('<loc') else self.check_script_conflicts
+
+    def recompute_conflicts(self) -> None:
+        """Rebuilds the conflict set from the merged definitions under the current settings.
+
+        Useful after changing `non_conflict_keywords`, `conflict_check_range` or the\
+            `check_script_conflicts`/`check_loc_conflicts` toggles, files are not re-parsed.
+        Entries of `identical_definitions` whose enabled sources now differ are dropped and reported again.
+        """
+        self.conflict_issues = {}
+        self.conflict_identifiers = []
+        if not self.conflict_check_range:
+            return
+        for def_node in self._iter_merged_nodes():
+            if not self._checks_conflicts_in(def_node):
+                continue
+            for key, value in def_node.items():
+                if key in non_conflict_keywords or self._still_identical((value.rel_dir.as_posix(), key), value):
+                    continue
+                if value.has_conflict():
+                    self.conflict_identifiers.append(value)
+        for obj in self.conflict_identifiers:
+            self.conflict_issues[(obj.rel_dir.as_posix(),obj.name)] = obj.sources
+
+    def _extract_definitions_multiprocess(self, file_entries:list[SourceEntry], max_workers:Optional[int]= None, progress: Optional[Callable[[int, int, str], None]] = None, stage: str = "txt"):
         """Extracts definitions using multiprocessing for better performance."""
-        futures = run_multiprocess(ModManager._extract_file_definitions, file_entries, max_workers=max_workers or os.cpu_count() or 4)
-        for fut in as_completed(futures):
-            file_entry, definitions, err = fut.result()
-            if err:
-                logger.error("Error parsing %s: %s", file_entry.file, str(err))
-                continue            
-            # based on the acquired definitions, add to define_table
-            has_conflict = self.add_definition(file_entry, definitions)
+        # the executor is kept open while results are consumed, so progress is reported as files finish
+        # and a cancellation drops the files that haven't started yet
+        func = partial(ModManager._extract_file_definitions, **self._parse_options())
+        with ProcessPoolExecutor(max_workers=max_workers or os.cpu_count() or 4) as executor:
+            pending = {executor.submit(func, file_entry) for file_entry in file_entries}
+            done = 0
+            while pending:
+                finished, pending = wait(pending, timeout=0.2, return_when=FIRST_COMPLETED)
+                if self._cancel is not None and self._cancel.is_set():
+                    executor.shutdown(wait=False, cancel_futures=True)
+                    raise ExtractionCancelled("cancelled")
+                for fut in finished:
+                    done += 1
+                    file_entry, definitions, err = fut.result()
+                    if progress is not None: # called here in the main process, not in the workers
+                        progress(done, len(file_entries), stage)
+                    if err:
+                        logger.error("Error parsing %s: %s", file_entry.file, str(err))
+                        self.failed_files.append((file_entry.file, str(err)))
+                        continue            
+                    # based on the acquired definitions, add to define_table
+                    has_conflict = self.add_definition(file_entry, definitions)
         for obj in self.conflict_identifiers:
             self.conflict_issues[(obj.rel_dir.as_posix(),obj.name)] = obj.sources
             # for mod_id in obj.sources.keys():
@@ -337,51 +806,100 @@ class ModManager:
             return True
         return False
     
-    def _build_file_tree(self, mod_list:ModList[str], process_max_workers:Optional[int]= None):
+    def _build_file_tree(self, mod_list:ModList[str], process_max_workers:Optional[int]= None, progress: Optional[Callable[[int, int, str], None]] = None):
         """Builds the file tree representation of the mod structure.
         
         Args:
             mod_list (ModList): List of mods to include in the file tree.
         """
         file_entries: dict[str, list[SourceEntry]] = {"txt": [], "yml":[],"other": []}
+        self.built_mods.extend(key for key in mod_list.keys() if key not in self.built_mods)
         t0=time.perf_counter()    
         if process_max_workers is not None and process_max_workers > 1:
             mod_entries = run_multithread(self._get_mod_file_entries, mod_list.values(), max_workers=process_max_workers)
-            for mod_entry in mod_entries:
+            for done, mod_entry in enumerate(mod_entries, 1):
                 file_entries["txt"].extend(mod_entry["txt"])
                 file_entries["yml"].extend(mod_entry["yml"])
                 file_entries["other"].extend(mod_entry["other"])
+                if progress is not None:
+                    progress(done, len(mod_list), "collect")
+                self._check_cancelled()
         else:
-            for mod_info in mod_list.values():            
+            for done, mod_info in enumerate(mod_list.values(), 1):            
                 mod_file_entries = self._get_mod_file_entries(mod_info)
                 file_entries["txt"].extend(mod_file_entries["txt"])
                 file_entries["yml"].extend(mod_file_entries["yml"])
                 file_entries["other"].extend(mod_file_entries["other"])
+                if progress is not None:
+                    progress(done, len(mod_list), "collect")
+                self._check_cancelled()
         
         logger.debug("File entries collected in %.2f seconds", (t1:=time.perf_counter()) - t0)
-        for file_entry in file_entries["other"]:
-            self.define_table.add_file(file_entry)
+        for done, file_entry in enumerate(file_entries["other"], 1):
+            self._setdefault_tree_node(
+                file_entry.rel_path, DefinitionFileNode(file_entry.file.name, file_entry.rel_path)
+            ).set_source(file_entry)
+            if progress is not None:
+                progress(done, len(file_entries["other"]), "other")
+        self._check_cancelled()
         t2 = time.perf_counter()
         logger.debug("Other files added in %.2f seconds", (t2:=time.perf_counter())-t1)
         if process_max_workers is not None and process_max_workers > 1:
             # This runs multithreaded/multiprocessed, Do NOT put it in the for loop
-            self._extract_definitions_multiprocess(file_entries["txt"], max_workers=process_max_workers)
-            self._extract_definitions_multiprocess(file_entries["yml"], max_workers=process_max_workers)
+            self._extract_definitions_multiprocess(file_entries["txt"], max_workers=process_max_workers, progress=progress, stage="txt")
+            self._extract_definitions_multiprocess(file_entries["yml"], max_workers=process_max_workers, progress=progress, stage="yml")
         else:
-            self._extract_definitions(file_entries["txt"])
-            self._extract_definitions(file_entries["yml"])
-        logger.debug("Definitions extracted in %.2f seconds", time.perf_counter()-t2)
+            self._extract_definitions(file_entries["txt"], progress, "txt")
+            self._extract_definitions(file_entries["yml"], progress, "yml")
+        logger.debug("Definitions extracted in %.2f seconds", (t3:=time.perf_counter())-t2)
+        self.extraction_stats = {
+            "files": sum(len(entries) for entries in file_entries.values()),
+            "parsed_files": len(file_entries["txt"]) + len(file_entries["yml"]),
+            "nodes": sum(self._count_nodes(node) for nodes in self.parsed_files.values() for node in nodes),
+            "conflicts": len(self.conflict_identifiers),
+            "phase_seconds": {"collect": t1-t0, "other": t2-t1, "extract": t3-t2},
+            "cache_hit_rate": self._cache_hit_rate(),
+        }
         
-    def get_rel_path(self, abs_path: str|Path) -> Optional[Path]:
-        """Gets the relative path of a file with respect to the mod directories."""
-        abs_path = Path(abs_path)
-        if abs_path.is_relative_to(self.MODS_DIR):
-            rel_path = abs_path.relative_to(self.MODS_DIR)
-        elif abs_path.is_relative_to(self.WORKSHOP_DIR):
-            rel_path = abs_path.relative_to(self.WORKSHOP_DIR)
-        else:    
+    def _cache_hit_rate(self) -> Optional[float]:
+        """Share of the parsed files loaded from the parse cache, None if caching is disabled."""
+        if self.cache_dir is None:
             return None
-        return rel_path.relative_to(rel_path.parts[0])
+        file_nodes = [node for nodes in self.parsed_files.values() for node in nodes]
+        if not file_nodes:
+            return 0.0
+        return sum(getattr(node, 'from_cache', False) for node in file_nodes) / len(file_nodes)
+
+    @staticmethod
+    def _count_nodes(node: DefinitionNode) -> int:
+        return 1 + sum(ModManager._count_nodes(child) for child in node.values())
+
+    def last_extraction_stats(self) -> dict:
+        """Returns the statistics of the last file tree build.
+        
+        Keys: "files", "parsed_files", "nodes", "conflicts", "phase_seconds" (collect/other/extract), "cache_hit_rate"
+        """
+        return dict(self.extraction_stats)
+        
+    def get_rel_path(self, abs_path: str|Path, mod: Optional[Mod] = None) -> Optional[Path]:
+        """Gets the relative path of a file with respect to the mod directories.
+        
+        The `root_dir` of `mod` is tried first, then the global mods/workshop dirs, then the `root_dir` of other mods.\
+            Mods installed elsewhere fall back to the path of `mod`, or of the mod containing the file.
+        """
+        abs_path = Path(abs_path)
+        roots = [mod.root_dir] if mod is not None and mod.root_dir is not None else []
+        roots += [Path(self.MODS_DIR), Path(self.WORKSHOP_DIR)]
+        roots += [m.root_dir for m in self.mod_list.values() if m.root_dir is not None]
+        for root in roots:
+            if abs_path.is_relative_to(root) and abs_path != root:
+                rel_path = abs_path.relative_to(root)
+                return rel_path.relative_to(rel_path.parts[0])
+        mod_paths = [mod.path] if mod is not None else [m.path for m in self.mod_list.values()]
+        containing = [path for path in mod_paths if path.parts and abs_path.is_relative_to(path) and abs_path != path]
+        if containing:
+            return abs_path.relative_to(max(containing, key=lambda path: len(path.parts)))
+        return None
     
     def dump_conflicts_to_json(self, output_path: str|Path):
         """Dumps the conflict issues to a JSON file for further analysis."""
@@ -399,5 +917,808 @@ class ModManager:
             json.dump(results, f, ensure_ascii=False, indent=4)
         logger.info("Conflict issues dumped to %s", output_path)
 
+    def _priority(self, mod: Mod) -> int:
+        """Returns the priority of a mod when resolving conflict winners, higher wins."""
+        return self.priority_override.get(mod.dup_name, mod.load_order)
+
+    def set_priority_override(self, name: str, priority: int) -> None:
+        """Makes mod `name` resolve conflicts with `priority` instead of its load order, the launch order is unchanged.
+        
+        With an enabled `replace_path` mod the dropped files may change, so all parsed files are merged again.
+        """
+        self.priority_override[name] = priority
+        self._remerge_if_replacing()
+
+    def clear_priority_override(self, name: Optional[str] = None) -> None:
+        """Removes the priority override of mod `name`, or all overrides if `name` is None."""
+        if name is None:
+            self.priority_override.clear()
+        else:
+            self.priority_override.pop(name, None)
+        self._remerge_if_replacing()
+
+    def _remerge_if_replacing(self) -> None:
+        """Re-evaluates the `replace_path` drops after a priority change, see `_remerge`."""
+        if self._merge_order and any(self._replace_paths(mod) for mod in self.mod_list.enabled):
+            self._remerge()
+
+    def get_priority_override(self, name: str) -> Optional[int]:
+        """Returns the priority override of mod `name`, None if it uses its load order."""
+        return self.priority_override.get(name)
+
+    def highest_priority(self, names: Iterable[str]) -> Optional[str]:
+        """Returns the mod among `names` that would win a conflict, i.e. the one with the highest load order or priority override."""
+        mods = [mod for name in names if (mod := self.mod_list.get(name)) is not None]
+        if not mods:
+            return None
+        return max(mods, key=self._priority).dup_name
+
+    def _get_winner(self, sources: SourceList) -> Optional[SourceEntry]:
+        """Returns the enabled source with the highest priority, which wins the conflict in game."""
+        enabled = [src for src in sources.values() if src.enabled and src.mod is not None]
+        if not enabled:
+            return None
+        return max(enabled, key=lambda src: self._priority(src.mod))
+
+    def suggest_resolutions(self) -> list[dict]:
+        """Suggests a resolution for each conflict in `conflict_issues`.
+
+        Returns:
+            list[dict]: One entry per conflict with keys:
+                - "path"       : "<rel_dir>::<identifier>"
+                - "winner"     : recommended winner (a compatch if one participates, else the load order winner)
+                - "losers"     : the other participating mods
+                - "resolution" : "compatch", "replaces", "replace_path" or None if the conflict is unresolved
+        """
+        suggestions = []
+        for (rel_dir, identifier), sources in self.conflict_issues.items():
+            winner_src = self._get_winner(sources)
+            if winner_src is None or winner_src.mod is None:
+                continue
+            mods = [src.mod for src in sources.get_enabled().values() if src.mod is not None]
+            winner, resolution = winner_src.mod, None
+            for mod in mods:
+                others = {other.name for other in mods if other is not mod}
+                if others and others.issubset(mod.dependencies):
+                    winner, resolution = mod, "compatch"
+                    break
+            else:
+                losers = {mod.name for mod in mods if mod is not winner}
+                if losers and losers.issubset(winner.replaces):
+                    resolution = "replaces"
+                elif any(Path(rel_dir).is_relative_to(path) for path in self._replace_paths(winner)):
+                    resolution = "replace_path"
+            suggestions.append({
+                "path": f"{rel_dir}::{identifier}",
+                "winner": winner.dup_name,
+                "losers": [mod.dup_name for mod in mods if mod is not winner],
+                "resolution": resolution,
+            })
+        return suggestions
+
+    def iter_conflicts(self) -> Iterable[tuple[str, str, list[str]]]:
+        """Lazily yields ("<rel_dir>::<identifier>", winner, losers) per conflict, the winner is resolved on demand."""
+        for (rel_dir, identifier), sources in self.conflict_issues.items():
+            winner = self._get_winner(sources)
+            if winner is None or winner.mod is None:
+                continue
+            losers = [src.mod.dup_name for src in sources.get_enabled().values() if src.mod is not None and src is not winner]
+            yield f"{rel_dir}::{identifier}", winner.mod.dup_name, losers
+
+    def potential_fixes(self) -> list[tuple[str, str]]:
+        """Suggests disabled mods, e.g. compatches, that would resolve an active conflict if enabled.
+        
+        A disabled mod qualifies if its `replaces` names all mods of the conflict, or one of its `replace_path`s covers the conflict dir.
+        Returns:
+            list[tuple[str, str]]: ("<rel_dir>::<identifier>", disabled mod name) pairs
+        """
+        disabled = self.mod_list.disabled
+        fixes = []
+        for (rel_dir, identifier), sources in self.conflict_issues.items():
+            names = {src.mod.name for src in sources.get_enabled().values() if src.mod is not None}
+            if len(names) < 2:
+                continue
+            for mod in disabled:
+                if names.issubset(mod.replaces) or any(Path(rel_dir).is_relative_to(path) for path in self._replace_paths(mod)):
+                    fixes.append((f"{rel_dir}::{identifier}", mod.dup_name))
+        return fixes
+
+    @staticmethod
+    def _iter_value_nodes(node: DefinitionNode, path: Path) -> Iterable[tuple[Path, DefinitionNode]]:
+        """Yields (identifier path, value node) for every value node below `node`."""
+        for key, child in node.items():
+            if isinstance(child, DefinitionValueNode):
+                yield path/key, child
+            else:
+                yield from ModManager._iter_value_nodes(child, path/key)
+
+    def dependents_of(self, name: str) -> list[tuple[str, Path]]:
+        """Lists the script values of other mods that reference identifiers defined by mod `name`.
+
+        Returns:
+            list[tuple[str, Path]]: (referencing mod name, identifier path) pairs,\
+                e.g. ("Mod B", Path("common/traits/my_trait/opposites"))
+        """
+        defined = {
+            key for file_node in self.parsed_files.get(name, [])
+            if file_node.name.lower().endswith(".txt")
+            for key in file_node.keys() if key not in non_conflict_keywords
+        }
+        dependents: dict[tuple[str, Path], None] = {}
+        for mod_name, file_nodes in self.parsed_files.items():
+            if mod_name == name:
+                continue
+            for file_node in file_nodes:
+                if not file_node.name.lower().endswith(".txt"):
+                    continue
+                for path, value_node in self._iter_value_nodes(file_node, file_node.rel_dir):
+                    values = value_node.value if isinstance(value_node.value, list) else [value_node.value]
+                    if any(value in defined for value in values):
+                        dependents[(mod_name, path)] = None
+        return list(dependents)
+
+    def _iter_parsed_files(self, suffix: str, enabled_only: bool = True) -> Iterable[tuple[SourceEntry, DefinitionNode]]:
+        """Yields (source, parsed file node) for parsed files with the given suffix, lowest `_priority` first."""
+        files = [
+            (file_node.source, file_node)
+            for file_nodes in self.parsed_files.values() for file_node in file_nodes
+            if file_node.name.lower().endswith(suffix) and file_node.source is not None
+            and (file_node.source.enabled or not enabled_only) and not getattr(file_node, 'replaced_by', None)
+        ]
+        # within a mod, loc files in a "replace" folder come last so they take priority
+        files.sort(key=lambda item: (
+            self._priority(item[0].mod) if item[0].mod is not None else item[0].load_order,
+            item[0].name or "", self._is_loc_replace(item[0].rel_path)
+        ))
+        yield from files
+
+    def loc_map(self, language: Optional[str] = None) -> dict[str, str]:
+        """Returns the effective localization key -> value map of enabled mods, higher priority wins (see `_priority`).
+        
+        Args:
+            language (str, optional): Defaults to `ModManager.language`.
+        """
+        language = language or self.language
+        result: dict[str, str] = {}
+        for _, file_node in self._iter_parsed_files(f"l_{language}.yml"):
+            for key, value in file_node.items():
+                if isinstance(value, DefinitionValueNode):
+                    result[key] = str(value.value)
+        return result
+
+    def loc_override_chain(self, key: str, language: Optional[str] = None) -> list[tuple[str, str, Path]]:
+        """Returns every (mod name, value, file) defining a loc key among enabled mods, from the lowest precedence to the winner.
+        
+        Uses the same order as `loc_map`, so "replace" folders come after the other loc files of their mod.
+        """
+        language = language or self.language
+        return [
+            (source.name or "", str(file_node[key].value), source.file)
+            for source, file_node in self._iter_parsed_files(f"l_{language}.yml")
+            if isinstance(file_node.get(key), DefinitionValueNode)
+        ]
+
+    def conflict_pairs(self) -> dict[tuple[str, str], int]:
+        """Counts the conflicts between each pair of mods, keyed by the alphabetically sorted pair of names."""
+        pairs: dict[tuple[str, str], int] = {}
+        for sources in self.conflict_issues.values():
+            names = sorted(set(sources.keys()))
+            for i, name_a in enumerate(names):
+                for name_b in names[i+1:]:
+                    pairs[(name_a, name_b)] = pairs.get((name_a, name_b), 0) + 1
+        return pairs
+
+    def export_conflict_dot(self) -> str:
+        """Exports the conflict graph in Graphviz DOT format, edges are weighted by the number of conflicts."""
+        lines = ["graph conflicts {"]
+        pairs = self.conflict_pairs()
+        for name in sorted({name for pair in pairs for name in pair}):
+            lines.append(f"    {json.dumps(name)};")
+        for (name_a, name_b), count in sorted(pairs.items()):
+            lines.append(f"    {json.dumps(name_a)} -- {json.dumps(name_b)} [label=\"{count}\", weight={count}];")
+        lines.append("}")
+        return "\n".join(lines) + "\n"
+
+    def _owner_name(self, node: DefinitionNode) -> Optional[str]:
+        """Returns the name of the mod whose definition of `node` is in effect."""
+        owner = self._get_winner(node.sources)
+        if owner is None and node.sources: # no enabled source, report the one that would win if enabled
+            with_mod = [src for src in node.sources.values() if src.mod is not None]
+            owner = max(with_mod, key=lambda src: self._priority(src.mod)) if with_mod else node.get_sources()[-1]
+        return owner.name if owner else None
+
+    def unlocalized_identifiers(self, dirs: Iterable[str|Path]) -> list[tuple[str, Optional[str]]]:
+        """Lists identifiers in the given content dirs (e.g. `common/traits`) without a localization key of the same name.
+        
+        Returns:
+            list[tuple[str, Optional[str]]]: (identifier, mod name) pairs
+        """
+        loc_node = self.identifier_index.get('localization')
+        loc_keys = set(loc_node.keys()) if loc_node is not None else set()
+        return [
+            (node.name, self._owner_name(node))
+            for dirpath in dirs
+            for node in self.identifiers_in(dirpath)
+            if node.name not in loc_keys and node.name not in non_conflict_keywords
+        ]
+
+    def broken_file_refs(self, extensions: Iterable[str] = (".dds",)) -> list[tuple[str, str]]:
+        """Lists script values referencing files with the given extensions that exist in no mod nor the game.
+        
+        Returns:
+            list[tuple[str, str]]: (referenced path, referencing mod name) pairs
+        """
+        extensions = tuple(ext.lower() for ext in extensions)
+        roots = [Path(self.GAME_DIR), *(mod.path for mod in self.mod_list.values())]
+        broken: dict[tuple[str, str], None] = {}
+        for source, file_node in self._iter_parsed_files(".txt", enabled_only=False):
+            for _, value_node in self._iter_value_nodes(file_node, file_node.rel_dir):
+                values = value_node.value if isinstance(value_node.value, list) else [value_node.value]
+                for value in values:
+                    ref = str(value).strip('"').replace('\\', '/').lstrip('/')
+                    if not ref.lower().endswith(extensions):
+                        continue
+                    try:
+                        found = self._get_tree_node(ref) is not None or any((root/ref).is_file() for root in roots)
+                    except ValueError: # path traversal
+                        found = False
+                    if not found:
+                        broken[(ref, source.name or "")] = None
+        return list(broken)
+
+    def silent_removals(self) -> list[tuple[str, Path, str]]:
+        """Lists identifiers dropped because a higher priority mod replaces the whole file without them.
+        
+        Returns:
+            list[tuple[str, Path, str]]: (removed identifier, file relative path, losing mod name)
+        """
+        versions: dict[Path, list[tuple[SourceEntry, DefinitionNode]]] = {}
+        for source, file_node in self._iter_parsed_files(".txt"):
+            versions.setdefault(source.rel_path, []).append((source, file_node))
+        removals = []
+        for rel_path, file_versions in versions.items():
+            if len(file_versions) < 2:
+                continue
+            winner_source, winner_node = max(file_versions, key=lambda item: self._priority(item[0].mod))
+            for source, file_node in file_versions:
+                if source is winner_source:
+                    continue
+                for key in file_node.keys():
+                    if key not in winner_node and key not in non_conflict_keywords:
+                        removals.append((key, rel_path, source.name or ""))
+        return removals
+
+    def extract_per_mod(self, max_depth: Optional[int] = None) -> dict[str, DefinitionDirectoryNode]:
+        """Parses each mod into its own tree, without merging definitions across mods.
+        
+        Args:
+            max_depth (int, optional): Script parsing depth, defaults to `max_def_depth`.
+        Returns:
+            dict[str, DefinitionDirectoryNode]: mod name -> root of the mod's tree
+        """
+        trees: dict[str, DefinitionDirectoryNode] = {}
+        options = self._parse_options()
+        if max_depth is not None:
+            options["max_def_depth"] = max_depth
+        for name, mod in self.mod_list.items():
+            root = trees[name] = DefinitionDirectoryNode(name, "./")
+            file_entries = self._get_mod_file_entries(mod)
+            for file_entry in file_entries["txt"] + file_entries["yml"]:
+                _, definitions, err = self._extract_file_definitions(file_entry, **options)
+                if definitions is None:
+                    logger.error("Error parsing %s: %s", file_entry.file, str(err))
+                    continue
+                root.setdefault_by_dir(file_entry.rel_path, definitions)
+        return trees
+
+    def mod_content_breakdown(self, name: str) -> dict[str, int]:
+        """Counts the identifiers mod `name` defines per content directory, e.g. {"common/traits": 12, "events": 40}."""
+        identifiers: dict[str, set[str]] = {}
+        for file_node in self.parsed_files.get(name, []):
+            keys = identifiers.setdefault(file_node.rel_dir.as_posix(), set())
+            keys.update(key for key in file_node.keys() if key not in non_conflict_keywords)
+        return {dirpath: len(keys) for dirpath, keys in sorted(identifiers.items())}
+
+    @staticmethod
+    def _replace_paths(mod: Mod) -> list[Path]:
+        """Returns the directories a mod replaces through `replace_path`."""
+        paths = [path for path in mod.replace_paths if path.parts]
+        if mod.replace_path is not None and mod.replace_path.parts and mod.replace_path not in paths:
+            paths.insert(0, mod.replace_path)
+        return paths
+
+    def _replaced_by(self, file_entry: SourceEntry) -> Optional[Mod]:
+        """Returns the enabled higher priority mod whose `replace_path` drops the file, if any."""
+        mod = file_entry.mod
+        if mod is None:
+            return None
+        rel_dir = file_entry.rel_path.parent
+        for other in self.mod_list.enabled:
+            if other is mod or self._priority(other) <= self._priority(mod):
+                continue
+            if any(rel_dir.is_relative_to(path) for path in self._replace_paths(other)):
+                return other
+        return None
+
+    def file_tree(self, effective: bool = False) -> DefinitionNode:
+        """Returns the file tree of the last build.
+        
+        With `effective`, returns a standalone copy without the files dropped by a higher priority mod's\
+            `replace_path`, as the game loads it. Each mod providing a file is checked, unparsed files (e.g. .gui, .dds)\
+            included, and the highest priority remaining one is kept. Directories that lost files get `replaced_by`\
+            set to the replacing mod.
+        """
+        if not effective:
+            return self.define_table
+        providers: dict[Path, list[tuple[SourceEntry, DefinitionNode]]] = {}
+        for file_nodes in self.parsed_files.values():
+            for file_node in file_nodes:
+                if file_node.source is not None:
+                    providers.setdefault(file_node.source.rel_path, []).append((file_node.source, file_node))
+        return self._effective_copy(self.define_table, providers)
+
+    def _effective_copy(self, node: DefinitionNode, providers: dict[Path, list[tuple[SourceEntry, DefinitionNode]]]) -> DefinitionNode:
+        clone = DefinitionDirectoryNode(node.name, node.rel_dir)
+        for key, child in node.items():
+            if isinstance(child, DefinitionDirectoryNode):
+                clone[key] = self._effective_copy(child, providers)
+                continue
+            if child.type == 'virtual' or child.source is None: # merged definitions already leave replaced files out
+                clone[key] = child.to_standalone_tree()
+                continue
+            # the node in the tree is only the first file to arrive, parsed files of the other mods are in `parsed_files`
+            candidates = providers.get(child.source.rel_path) or [(source, child) for source in child.sources.values()]
+            kept = [(source, file_node) for source, file_node in candidates if self._replaced_by(source) is None]
+            if not kept:
+                clone.replaced_by = self._replaced_by(candidates[-1][0]).dup_name
+                continue
+            _, file_node = max(kept, key=lambda item: self._priority(item[0].mod) if item[0].mod is not None else item[0].load_order)
+            copy = file_node.to_standalone_tree()
+            kept_sources = [source for source, _ in kept]
+            for source, _ in candidates:
+                if not any(source is kept_source for kept_source in kept_sources):
+                    copy.sources.pop(source.name or "", None)
+            clone[key] = copy
+        return clone
+
+    def replace_path_conflicts(self) -> list[tuple[str, str, Path]]:
+        """Lists pairs of enabled mods whose `replace_path` declarations overlap, only the later one is honored.
+        
+        Returns:
+            list[tuple[str, str, Path]]: (mod name, mod name, contested path), mods in load order
+        """
+        mods = sorted(self.mod_list.enabled, key=self._priority)
+        conflicts = []
+        for i, mod_a in enumerate(mods):
+            for mod_b in mods[i+1:]:
+                for path_a in self._replace_paths(mod_a):
+                    for path_b in self._replace_paths(mod_b):
+                        if path_a.is_relative_to(path_b) or path_b.is_relative_to(path_a):
+                            contested = max(path_a, path_b, key=lambda p: len(p.parts))
+                            conflicts.append((mod_a.dup_name, mod_b.dup_name, contested))
+        return conflicts
+
+    def mods_in_directory(self, dirpath: str|Path) -> list[tuple[str, int]]:
+        """Lists the mods defining identifiers under a content directory with their identifier counts, most first."""
+        dirpath = Path(dirpath)
+        counts = []
+        for name, file_nodes in self.parsed_files.items():
+            keys = {
+                key for file_node in file_nodes if file_node.rel_dir.is_relative_to(dirpath)
+                for key in file_node.keys() if key not in non_conflict_keywords
+            }
+            if keys:
+                counts.append((name, len(keys)))
+        counts.sort(key=lambda item: (-item[1], item[0]))
+        return counts
+
+    def deduplicate_identical(self) -> list[tuple[str, str]]:
+        """Removes conflicts where every enabled source defines the identifier identically.
+        
+        Definitions truncated by `max_def_depth` are never considered identical, their bodies are incomplete.
+        Returns:
+            list[tuple[str, str]]: the (rel_dir, identifier) conflicts that were removed
+        """
+        removed = [key for key, sources in self.conflict_issues.items() if self._is_identical(*key, sources)]
+        for key in removed:
+            self.identical_definitions.add(key)
+            del self.conflict_issues[key]
+        self.conflict_identifiers = [
+            obj for obj in self.conflict_identifiers
+            if (obj.rel_dir.as_posix(), obj.name) not in self.identical_definitions
+        ]
+        return removed
+
+    def _is_identical(self, rel_dir: str, identifier: str, sources: SourceList) -> bool:
+        """Checks whether every enabled source defines the identifier with the same script."""
+        scripts = set()
+        for source in sources.get_enabled().values():
+            file_node, node = self._find_mod_definition(source.name or "", rel_dir, identifier)
+            if node is None or getattr(file_node, 'truncated', False) or self._is_truncated(node):
+                return False
+            scripts.add(node.to_script())
+        return len(scripts) == 1
+
+    def _still_identical(self, key: tuple[str, str], node: DefinitionNode) -> bool:
+        """Re-checks an entry of `identical_definitions` against the enabled sources, dropping it once they differ."""
+        if key not in self.identical_definitions:
+            return False
+        if self._is_identical(*key, node.sources):
+            return True
+        self.identical_definitions.discard(key)
+        return False
+
+    @staticmethod
+    def _brace_error_line(source: bytes) -> Optional[int]:
+        """Returns the 1-based line where brace nesting goes negative, or of the first unclosed `{`.\
+            Braces inside strings and `#` comments are ignored.
+        """
+        open_lines: list[int] = []
+        for line_no, line in enumerate(source.splitlines(), 1):
+            in_string = False
+            i = 0
+            while i < len(line):
+                char = line[i:i+1]
+                if in_string:
+                    if char == b'\\':
+                        i += 1 # skip the escaped character
+                    elif char == b'"':
+                        in_string = False
+                elif char == b'"':
+                    in_string = True
+                elif char == b'#':
+                    break
+                elif char == b'{':
+                    open_lines.append(line_no)
+                elif char == b'}':
+                    if not open_lines:
+                        return line_no
+                    open_lines.pop()
+                i += 1
+        return open_lines[0] if open_lines else None
+
+    def brace_check(self, enabled_only: bool = True) -> list[tuple[Path, int]]:
+        """Scans the txt and gui files of the mods for unbalanced braces, a cheap lint before full parsing.
+        
+        Returns:
+            list[tuple[Path, int]]: (file, line) where the nesting goes negative or the first unclosed `{`
+        """
+        errors = []
+        mods = self.mod_list.enabled if enabled_only else self.mod_list.values()
+        for mod in mods:
+            file_entries = self._get_mod_file_entries(mod)
+            for file_entry in file_entries["txt"] + file_entries["other"]:
+                if file_entry.file.suffix.lower() not in (".txt", ".gui"):
+                    continue
+                try:
+                    line_no = self._brace_error_line(file_entry.file.read_bytes())
+                except OSError as e:
+                    logger.error("Error reading %s: %s", file_entry.file, str(e))
+                    continue
+                if line_no is not None:
+                    errors.append((file_entry.file, line_no))
+        return errors
+
+    def effective_value(self, path: str|Path) -> Optional[tuple[str, str]]:
+        """Returns the value of the load order winning definition at an identifier path, e.g. `common/traits/brave/ai_boldness`.
+        
+        Blocks are returned serialized to script, e.g. "{\\n    x = 1\\n}". The script analog of `loc_map`.
+        Returns:
+            Optional[tuple[str, str]]: (value, winning mod name), or None if the path isn't defined by an enabled mod
+        """
+        parts = Path(path).parts
+        for i in range(len(parts) - 1, 0, -1):
+            rel_dir = Path(*parts[:i]).as_posix()
+            def_node = self.identifier_index.get(rel_dir)
+            if def_node is not None and parts[i] in def_node:
+                break
+        else:
+            return None
+        winner = self._get_winner(def_node[parts[i]].sources)
+        if winner is None or winner.name is None:
+            return None
+        node = self.get_mod_definition(winner.name, rel_dir, parts[i])
+        for key in parts[i+1:]:
+            if node is None or key not in node:
+                return None
+            node = node[key]
+        if node is None:
+            return None
+        if isinstance(node, DefinitionValueNode):
+            value = f"{{ {' '.join(map(str, node.value))} }}" if isinstance(node.value, list) else str(node.value)
+        else:
+            value = f"{{\n{node.to_script(1)}\n}}" if len(node) else "{ }"
+        return value, winner.name
+
+    def outdated_mods(self, current_version: str) -> list[str]:
+        """Lists the mods included in the file tree whose `supported_version` is older than `current_version`, e.g. "1.12.4"."""
+        return [
+            name for name in self.built_mods
+            if (mod := self.mod_list.get(name)) is not None and mod.is_outdated(current_version)
+        ]
+
+    def search(self, query: str, limit: int = 20, min_ratio: float = 0.6) -> list[DefinitionNode]:
+        """Searches the identifiers of all content directories, ranked by relevance.
+        
+        Exact name matches come first, then prefix, then substring, then fuzzy matches\
+            (`difflib` ratio of at least `min_ratio`). Matching is case-insensitive.
+        """
+        query = query.lower()
+        ranked = []
+        for def_node in self.identifier_index.values():
+            for key, node in def_node.items():
+                name = key.lower()
+                ratio = SequenceMatcher(None, query, name).ratio()
+                if name == query:
+                    tier = 0
+                elif name.startswith(query):
+                    tier = 1
+                elif query in name:
+                    tier = 2
+                elif ratio >= min_ratio:
+                    tier = 3
+                else:
+                    continue
+                ranked.append((tier, -ratio, key, node.rel_dir.as_posix(), node))
+        ranked.sort(key=lambda item: item[:4])
+        return [item[4] for item in ranked[:limit]]
+
+    def export_loc_csv(self, path: str|Path, language: Optional[str] = None) -> None:
+        """Writes the effective localization of enabled mods to a CSV file for translation tools.
+        
+        Columns: key, value, source_mod, file (relative to the mod), line
+        Args:
+            language (str, optional): Defaults to `ModManager.language`.
+        """
+        language = language or self.language
+        rows: dict[str, tuple] = {}
+        for source, file_node in self._iter_parsed_files(f"l_{language}.yml"):
+            for key, value in file_node.items():
+                if isinstance(value, DefinitionValueNode):
+                    rows[key] = (key, value.value, source.name, source.rel_path.as_posix(), getattr(value, 'line', None))
+        with open(path, "w", encoding="utf-8", newline="") as f:
+            writer = csv.writer(f)
+            writer.writerow(["key", "value", "source_mod", "file", "line"])
+            writer.writerows(rows.values())
+
+    def import_loc_csv(self, csv_path: str|Path, output_path: str|Path, language: Optional[str] = None) -> int:
+        """Writes the key/value rows of a CSV exported by `export_loc_csv` to a new localization yml.
+        
+        Returns:
+            int: Number of keys written
+        """
+        language = language or self.language
+        with open(csv_path, "r", encoding="utf-8", newline="") as f:
+            rows = [(row["key"], row["value"]) for row in csv.DictReader(f) if row.get("key")]
+        output_path = Path(output_path)
+        output_path.parent.mkdir(parents=True, exist_ok=True)
+        with open(output_path, "w", encoding="utf-8-sig") as f: # CK3 requires UTF-8 with BOM
+            f.write(f"l_{language}:\n")
+            for key, value in rows:
+                f.write(f' {key}: "{value}"\n')
+        return len(rows)
+
+    def _index_key(self, file_node: DefinitionNode) -> str:
+        """Returns the `identifier_index` key of a parsed file's merged node."""
+        if file_node.name.lower().endswith(".yml"):
+            return self._loc_index_key(self._loc_node_name(file_node.name))
+        return file_node.rel_dir.as_posix()
+
+    def impact_of_enabling(self, name: str) -> dict[str, list]:
+        """Previews what enabling mod `name` would change against the currently enabled mods, without enabling it.
+        
+        The mod's files must be in the file tree, e.g. built with `include_disabled=True`.
+        Returns:
+            dict[str, list]: 
+                - "added"      : [(rel_dir, identifier)] not defined by any enabled mod
+                - "overridden" : [(rel_dir, identifier, current owner)] the mod would win by load order
+                - "conflicts"  : [(rel_dir, identifier, [enabled mods])] every identifier shared with enabled mods
+        """
+        mod = self.mod_list.get(name)
+        if mod is None:
+            raise KeyError(f"Mod not found: {name}")
+        impact: dict[str, list] = {"added": [], "overridden": [], "conflicts": []}
+        seen = set()
+        for file_node in self.parsed_files.get(name, []):
+            index_key = self._index_key(file_node)
+            def_node = self.identifier_index.get(index_key)
+            for key in file_node.keys():
+                if key in non_conflict_keywords or (index_key, key) in seen:
+                    continue
+                seen.add((index_key, key))
+                sources = def_node[key].sources if def_node is not None and key in def_node else SourceList()
+                others = [
+                    src.mod for src in sources.values()
+                    if src.enabled and src.mod is not None and src.mod is not mod
+                ]
+                if not others:
+                    impact["added"].append((index_key, key))
+                    continue
+                impact["conflicts"].append((index_key, key, [other.dup_name for other in others]))
+                owner = max(others, key=self._priority)
+                if self._priority(mod) > self._priority(owner):
+                    impact["overridden"].append((index_key, key, owner.dup_name))
+        return impact
+
+    def empty_definitions(self, dirs: Iterable[str|Path]) -> list[tuple[str, str]]:
+        """Lists identifiers defined as empty blocks (`trait_x = { }`) under the content directories, often stubs or mistakes.
+        
+        Identifiers truncated by `max_def_depth` and files cut short by the node budget are skipped, their bodies aren't parsed.
+        Returns:
+            list[tuple[str, str]]: (identifier, mod name)
+        """
+        dirs = [Path(dirpath) for dirpath in dirs]
+        empty = []
+        for name, file_nodes in self.parsed_files.items():
+            for file_node in file_nodes:
+                if getattr(file_node, 'truncated', False) or not any(file_node.rel_dir.is_relative_to(d) for d in dirs):
+                    continue
+                empty.extend(
+                    (key, name) for key, node in file_node.items()
+                    if node.type == 'identifier' and not len(node) and not getattr(node, 'truncated', False)
+                    and key not in non_conflict_keywords
+                )
+        return empty
+
+    def path_for_workshop_id(self, workshop_id: str|int) -> Optional[Path]:
+        """Returns the path of the mod in the file tree with the given Steam Workshop `remote_file_id`, e.g. one cited by error.log."""
+        workshop_id = str(workshop_id).strip()
+        for name in self.built_mods:
+            mod = self.mod_list.get(name)
+            if mod is not None and mod.remote_file_id and str(mod.remote_file_id).strip() == workshop_id:
+                return mod.path
+        return None
+
+    def dependencies_on_disabled(self) -> list[tuple[str, str]]:
+        """Lists enabled mods declaring a dependency on a mod of the mod list that is disabled, a common cause of broken playsets.
+        
+        Returns:
+            list[tuple[str, str]]: (mod name, disabled dependency name)
+        """
+        disabled = {mod.name for mod in self.mod_list.values() if not mod.enabled}
+        return [
+            (mod.dup_name, dependency)
+            for mod in self.mod_list.enabled
+            for dependency in mod.dependencies if dependency in disabled
+        ]
+
+    def set_mod_enabled(self, name: str, enabled: bool) -> None:
+        """Enables or disables a mod and updates the conflicts of only the identifiers it defines.
+        
+        Nothing is re-parsed, so a mod whose files weren't collected by the last build (e.g. a disabled mod\
+            skipped with the default `file_range`) can't be enabled this way, rebuild the file tree instead.
+            Toggling a mod with `replace_path` changes which files are dropped, so all parsed files are merged again.
+        Raises:
+            KeyError: the mod isn't in the mod list
+            ValueError: enabling a mod missing from the built file tree
+        """
+        mod = self.mod_list.get(name)
+        if mod is None:
+            raise KeyError(f"Mod not found: {name}")
+        if enabled and self.built_mods and name not in self.built_mods:
+            raise ValueError(f"Mod {name} was not collected by the last build, rebuild the file tree to enable it")
+        mod.enabled = enabled
+        if self._replace_paths(mod):
+            self._remerge()
+            return
+        if not self.conflict_check_range:
+            return
+        affected: dict[tuple[str, str], DefinitionNode] = {}
+        for file_node in self.parsed_files.get(name, []):
+            def_node = self.identifier_index.get(self._index_key(file_node))
+            if def_node is None or not self._checks_conflicts_in(def_node):
+                continue
+            for key in file_node.keys():
+                if key in def_node and key not in non_conflict_keywords:
+                    affected[(def_node[key].rel_dir.as_posix(), key)] = def_node[key]
+        self.conflict_identifiers = [
+            obj for obj in self.conflict_identifiers if (obj.rel_dir.as_posix(), obj.name) not in affected
+        ]
+        for key, node in affected.items():
+            self.conflict_issues.pop(key, None)
+            if not self._still_identical(key, node) and node.has_conflict():
+                self.conflict_identifiers.append(node)
+                self.conflict_issues[key] = node.sources
+
+    def unused_loc_keys(self, roots: Iterable[str|Path] = (), language: Optional[str] = None) -> list[tuple[str, str]]:
+        """Lists loc keys of enabled mods that nothing references, e.g. dead strings left after removing content.
+        
+        A key counts as used if a script value or identifier name under `roots` (all scripts if empty) matches it,\
+            another loc value references it (`$key$`), or it's derived from an identifier by `LOC_AUTO_PREFIXES`/`LOC_AUTO_SUFFIXES`.
+        Returns:
+            list[tuple[str, str]]: (loc key, mod name)
+        """
+        language = language or self.language
+        roots = [Path(root) for root in roots]
+        used: set[str] = set()
+        for _, file_node in self._iter_parsed_files(".txt"):
+            if roots and not any(file_node.rel_dir.is_relative_to(root) for root in roots):
+                continue
+            used.update(file_node.keys())
+            for _, value_node in self._iter_value_nodes(file_node, file_node.rel_dir):
+                values = value_node.value if isinstance(value_node.value, list) else [value_node.value]
+                used.update(str(value).strip('"') for value in values)
+        loc_files = list(self._iter_parsed_files(f"l_{language}.yml"))
+        for _, file_node in loc_files:
+            for value in file_node.values():
+                used.update(key for key, _ in getattr(value, 'references', []))
+        
+        def is_used(key: str) -> bool:
+            bases = {key}
+            bases.update(key[len(prefix):] for prefix in LOC_AUTO_PREFIXES if key.startswith(prefix))
+            bases.update(base[:-len(suffix)] for base in list(bases) for suffix in LOC_AUTO_SUFFIXES if base.endswith(suffix))
+            return not bases.isdisjoint(used)
+        
+        unused: dict[tuple[str, str], None] = {}
+        for source, file_node in loc_files:
+            for key in file_node.keys():
+                if not is_used(key):
+                    unused[(key, source.name or "")] = None
+        return list(unused)
+
+    def conflict_summary(self) -> dict[str, dict]:
+        """Returns the conflicts as "<rel_dir>::<identifier>" -> {"mods": [names in load order], "winner": name or None}."""
+        summary = {}
+        for (rel_dir, identifier), sources in self.conflict_issues.items():
+            winner = self._get_winner(sources)
+            summary[f"{rel_dir}::{identifier}"] = {
+                "mods": [src.name for src in sources.by_load_order()],
+                "winner": winner.name if winner else None,
+            }
+        return summary
+
+    def save_conflict_cache(self, path: str|Path) -> None:
+        """Saves only the `conflict_summary`, so a UI can show the conflicts without rebuilding the file tree."""
+        with open(path, "w", encoding="utf-8") as f:
+            json.dump(self.conflict_summary(), f, ensure_ascii=False, indent=4)
+
+    @staticmethod
+    def load_conflict_cache(path: str|Path) -> dict[str, dict]:
+        """Loads a conflict summary saved by `save_conflict_cache`."""
+        with open(path, "r", encoding="utf-8") as f:
+            return json.load(f)
+
+    def version_consistency(self) -> list[tuple[str, str, int]]:
+        """Returns (mod name, declared version, content fingerprint) for each mod in the list.
+        
+        Comparing two results shows mods whose content changed without a `version` bump, or the other way around.
+        """
+        return [(mod.dup_name, mod.version, mod.content_fingerprint()) for mod in self.mod_list.values()]
+
+    def suspected_typos(self, dirpath: str|Path, threshold: float = 0.85) -> list[tuple[str, str]]:
+        """Lists identifiers of a content directory that look like a misspelling of a more common one.
+        
+        An identifier is counted once per defining mod and once per script value referencing it.\
+            One seen only once is reported if its similarity (1 - edit distance / longer name length, a swap of\
+            adjacent characters counts as one edit)\
+            to a more common identifier is at least `threshold`.
+        Returns:
+            list[tuple[str, str]]: (typo candidate, nearest known identifier), e.g. ("martail", "martial")
+        """
+        counts = {node.name: len(node.sources) for node in self.identifiers_in(dirpath) if node.name not in non_conflict_keywords}
+        for file_nodes in self.parsed_files.values():
+            for file_node in file_nodes:
+                if not file_node.name.lower().endswith(".txt"):
+                    continue
+                for _, value_node in self._iter_value_nodes(file_node, file_node.rel_dir):
+                    values = value_node.value if isinstance(value_node.value, list) else [value_node.value]
+                    for value in values:
+                        if value in counts:
+                            counts[value] += 1
+        known = [name for name, count in counts.items() if count > 1]
+        typos = []
+        for name, count in counts.items():
+            if count != 1:
+                continue
+            best, best_ratio = None, threshold
+            for other in known:
+                longest = max(len(name), len(other))
+                ratio = 1 - _levenshtein(name, other, transpositions=True) / longest
+                if ratio >= best_ratio and (best is None or ratio > best_ratio or counts[other] > counts[best]):
+                    best, best_ratio = other, ratio
+            if best is not None:
+                typos.append((name, best))
+        return typos
+
     
     
\ No newline at end of file
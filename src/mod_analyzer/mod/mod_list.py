@@ -1,5 +1,7 @@
+import re
+from functools import lru_cache
 from pathlib import Path
-from typing import Any, Optional,Sequence, TypeVar, Generic
+from typing import Any, Iterable, Optional,Sequence, TypeVar, Generic
 from dataclasses import dataclass, field
 from indexed import IndexedOrderedDict
 import logging
@@ -8,6 +10,11 @@ from .descriptor import Mod
 
 pkg = (__package__ or __name__).split('.')[0]
 logger = logging.getLogger(pkg)
+PARAMETER_PATTERN = re.compile(r'\$([A-Za-z0-9_]+)\$')
+# node types ordered from the outermost to the innermost, a child may never rank below its parent
+TYPE_RANKS = {'root': 0, 'directory': 0, 'virtual': 1, 'file': 1, 'identifier': 2, 'value': 3}
+LOC_REFERENCE_PATTERN = re.compile(r'\$([A-Za-z0-9_.]+)(?:\|[^$]*)?\$') # $key$ or $key|U$
+JSON_PATH_KEY_PATTERN = re.compile(r"\.([A-Za-z_][A-Za-z0-9_]*)|\['((?:[^'\\]|\\.)*)'\]") # .key or ['any key']
 
 class ModList(IndexedOrderedDict, Generic[TypeVar('KeyType')]):    
     """Holds a list of mods and their information.
@@ -21,9 +28,13 @@ class ModList(IndexedOrderedDict, Generic[TypeVar('KeyType')]):
         # Initialize from dict of Mod instances + load order
         # load order from list of mod names
         mod_list = ModList(mods:dict[str, Mod], load_order:list[str]) 
+        
+        # raise on mods missing name/enabled/load_order/path instead of falling back to defaults
+        mod_list = ModList(mods:list[Mod], strict=True)
         ```
     """
-    def __init__(self, mod_list: Optional[Sequence[Mod]|dict[Any, Mod]] = None, load_order: Optional[list[str]] = None):
+    REQUIRED_ATTRS = ("name", "enabled", "load_order", "path")
+    def __init__(self, mod_list: Optional[Sequence[Mod]|dict[Any, Mod]] = None, load_order: Optional[list[str]] = None, strict: bool = False):
         super().__init__()
         self.duplicates:dict[str, int] = {}
         no_order_provided = load_order is None
@@ -41,6 +52,8 @@ class ModList(IndexedOrderedDict, Generic[TypeVar('KeyType')]):
         else:
             # self._load_order = load_order or []            
             for i, mod in enumerate(mod_list or []):
+                if strict:
+                    self.check_required_attrs(mod)
                 if mod.name is None:
                     mod.name = "unknown_"+str(len(self)+1)
                     logger.warning("Mod with no name found: %s", mod)
@@ -54,6 +67,14 @@ class ModList(IndexedOrderedDict, Generic[TypeVar('KeyType')]):
                     mod.load_order = i
                     # self._load_order.append(mod.name)
                     
+    @classmethod
+    def check_required_attrs(cls, mod: Mod) -> None:
+        """Raises AttributeError naming the first required attribute `mod` is missing."""
+        for attr in cls.REQUIRED_ATTRS:
+            value = getattr(mod, attr, None)
+            if value is None or (attr == "name" and not value) or (attr == "path" and not Path(value).parts):
+                raise AttributeError(f"Mod is missing required attribute '{attr}': {mod!r}")
+                    
     def add_duplicate(self, mod: Mod):
         """Renames duplicate mod names by appending a suffix: "#<number>"."""
         base_name = mod.name or "unknown"
@@ -181,6 +202,9 @@ class SourceList(IndexedOrderedDict, Generic[TypeVar('KeyType')]):
             if source.mod is not None:
                 mods.add_duplicate(source.mod)
         return mods
+    def by_load_order(self) -> list[SourceEntry]:
+        """Returns the sources ordered by load order (ties broken by name), so the winner is last."""
+        return sorted(self.values(), key=lambda src: (src.load_order, src.name or ""))
     def get_enabled(self) -> "SourceList":
         """Returns a SourceList of only enabled sources."""
         enabled_sources = SourceList()
@@ -200,6 +224,9 @@ class DefinitionNode(dict):
         self.sources: SourceList = SourceList()
         self.type:str = type
         self.parent: Optional["DefinitionNode"] = None
+        # 0-based (row, column) range of the definition in its source file, set by the parsers
+        self.start_point: Optional[tuple[int, int]] = None
+        self.end_point: Optional[tuple[int, int]] = None
         if source:
             self.set_source(source)
     def __bool__(self):
@@ -215,6 +242,131 @@ class DefinitionNode(dict):
     def source(self) -> Optional[SourceEntry]:
         return self.sources.values()[0] if self.sources else None
     
+    def set_type(self, node_type: str) -> None:
+        """Forces the type of this node, e.g. to mark a programmatically built node as "virtual".
+        
+        Raises:
+            ValueError: If the type is unknown or would break the type ordering against the parent or children.
+        """
+        if node_type not in TYPE_RANKS:
+            raise ValueError(f"Unknown node type: {node_type!r}, expected one of {list(TYPE_RANKS)}")
+        rank = TYPE_RANKS[node_type]
+        if self.parent is not None and TYPE_RANKS.get(self.parent.type, 0) > rank:
+            raise ValueError(f"A {node_type!r} node can't be a child of a {self.parent.type!r} node: {self!r}")
+        if node_type == 'value' and len(self):
+            raise ValueError(f"A 'value' node can't have children: {self!r}")
+        for child in self.values():
+            if TYPE_RANKS.get(child.type, 0) < rank:
+                raise ValueError(f"A {node_type!r} node can't contain a {child.type!r} node: {self!r}")
+        self.type = node_type
+    
+    def value_kind(self) -> str:
+        """Returns how the value was written: "scalar", "array", "tagged_array" or "block" for nested blocks."""
+        return 'block'
+    
+    def is_virtual(self) -> bool:
+        """Whether this is a synthetic node (e.g. `<def>`, `<loc>`) that doesn't exist on disk."""
+        return self.type == 'virtual'
+    
+    def is_synthetic_name(self) -> bool:
+        """Whether the name follows the synthetic naming convention: `<def>`, `<loc>`, `%root%`."""
+        return (self.name.startswith('<') and self.name.endswith('>')) or \
+            (self.name.startswith('%') and self.name.endswith('%'))
+    
+    def child_entries(self) -> list[tuple[str, "DefinitionNode"]]:
+        """Returns the (key, child node) pairs in insertion order.
+        
+        Children are held directly by this dict, there are no arena ids in Python, so this is `list(self.items())`.
+        """
+        return list(self.items())
+    
+    def siblings(self) -> list["DefinitionNode"]:
+        """Returns the other children of this node's parent in order, empty for the root."""
+        if self.parent is None:
+            return []
+        return [node for node in self.parent.values() if node is not self]
+    
+    def walk(self) -> Iterable["DefinitionNode"]:
+        """Lazily yields all descendants of this node in pre-order, this node excluded."""
+        return self.walk_depth(-1)
+    
+    def walk_depth(self, max_depth: int) -> Iterable["DefinitionNode"]:
+        """Like `walk`, but stops `max_depth` levels below this node (1 yields the children only), negative means no limit."""
+        stack: list[tuple[DefinitionNode, int]] = [(child, 1) for child in reversed(self.values())]
+        while stack:
+            node, depth = stack.pop()
+            yield node
+            if max_depth < 0 or depth < max_depth:
+                stack.extend((child, depth + 1) for child in reversed(node.values()))
+    
+    def json_path(self) -> str:
+        """Returns a JSONPath-like address of this node from the root, e.g. `$.common.traits['00_traits.txt'].brave`.
+        
+        Keys that aren't plain identifiers use the bracket form. Resolve it with `resolve_json_path` on the root.
+        """
+        keys = []
+        node = self
+        while node.parent is not None:
+            keys.append(next(key for key, child in node.parent.items() if child is node))
+            node = node.parent
+        path = "$"
+        for key in reversed(keys):
+            if re.fullmatch(r'[A-Za-z_][A-Za-z0-9_]*', key):
+                path += f".{key}"
+            else:
+                path += "['" + key.replace("\\", "\\\\").replace("'", "\\'") + "']"
+        return path
+    
+    def resolve_json_path(self, path: str) -> Optional["DefinitionNode"]:
+        """Returns the node at a path from `json_path`, relative to this node, or None if it doesn't exist.
+        
+        Raises:
+            ValueError: If the path is malformed.
+        """
+        if not path.startswith("$"):
+            raise ValueError(f"JSON path must start with '$': {path!r}")
+        node, pos = self, 1
+        while pos < len(path):
+            match = JSON_PATH_KEY_PATTERN.match(path, pos)
+            if match is None:
+                raise ValueError(f"Invalid JSON path at {pos}: {path!r}")
+            key = match.group(1) if match.group(1) is not None else re.sub(r"\\(.)", r"\1", match.group(2))
+            node = node.get(key)
+            if node is None:
+                return None
+            pos = match.end()
+        return node
+    
+    def get_start_point(self) -> Optional[tuple[int, int]]:
+        """Returns the 0-based (row, column) where the definition starts in its file, None if unknown."""
+        return self.start_point
+    
+    def get_end_point(self) -> Optional[tuple[int, int]]:
+        """Returns the 0-based (row, column) where the definition ends in its file, None if unknown."""
+        return self.end_point
+    
+    def get_start_line(self) -> Optional[int]:
+        """Returns the 1-based line where the definition starts, None if unknown."""
+        return self.start_point[0] + 1 if self.start_point is not None else None
+    
+    def get_end_line(self) -> Optional[int]:
+        """Returns the 1-based line where the definition ends, None if unknown."""
+        return self.end_point[0] + 1 if self.end_point is not None else None
+    
+    def get_sources(self) -> list[SourceEntry]:
+        """Returns the sources of this node in launch order, the last one wins in game unless a priority is overridden.
+        
+        Priority overrides live in the manager, use `ModManager.highest_priority` to get the effective winner.
+        """
+        return self.sources.by_load_order()
+    
+    def source_mods_info(self) -> list[tuple[str, int, bool]]:
+        """Returns (mod name, load order, enabled) of each source in launch order, as shown by the conflict view.
+        
+        Priority overrides aren't applied, the last entry isn't necessarily the winner.
+        """
+        return [(src.name or "", src.load_order, bool(src.enabled)) for src in self.get_sources()]
+    
     def setdefault(self, key: str, default: Any = None) -> Any:
         # this is required to properly call __setitem__ on new entries
         if key not in self:
@@ -255,8 +407,54 @@ class DefinitionNode(dict):
         else:
             super().update(__m or {}, **kwargs) #type: ignore
         
+    def new_nodes(self, specs: Iterable[tuple[str, str|Path, Optional[str]]]) -> list["DefinitionNode"]:
+        """Creates many child nodes at once from (name, rel_dir, type) specs and returns them.
+        
+        `type` is one of "directory", "file", "virtual", "identifier" (default when None) or "value".
+        """
+        nodes = []
+        for name, rel_dir, node_type in specs:
+            node = NODE_TYPES[node_type or 'identifier'](name, rel_dir)
+            self[name] = node
+            nodes.append(node)
+        return nodes
+    
+    @staticmethod
+    def _dir_parts(dirpath: str | Path) -> tuple[str, ...]:
+        """Splits a relative dir into its parts, resolving `..` so no `..` key is ever created.
+        
+        Raises:
+            ValueError: If the path traverses above the root.
+        """
+        parts: list[str] = []
+        for part in Path(dirpath).parts:
+            if part == '..':
+                if not parts:
+                    raise ValueError(f"Path traverses above the root: {dirpath}")
+                parts.pop()
+            elif part != '.':
+                parts.append(part)
+        return tuple(parts)
+    
+    def to_standalone_tree(self) -> "DefinitionNode":
+        """Deep copies this subtree into a new tree rooted at a copy of this node.
+        
+        Sources keep their file and mod name but are unlinked from the Mod instances.
+        """
+        clone = type(self).__new__(type(self))
+        clone.__dict__.update(self.__dict__)
+        clone.parent = None
+        clone.sources = SourceList()
+        for name, source in self.sources.items():
+            entry = SourceEntry(source.file, name=source.name)
+            entry.mod = None
+            clone.sources[name] = entry
+        for key, child in self.items():
+            clone[key] = child.to_standalone_tree()
+        return clone
+    
     def get_by_dir(self, dirpath: str | Path, default=None) -> Optional["DefinitionNode"]:
-        parts = Path(dirpath).parts
+        parts = self._dir_parts(dirpath)
         current_level = self
         for part in parts:
             current_level = current_level.get(part)
@@ -276,16 +474,103 @@ class DefinitionNode(dict):
         file_descriptor = file_descriptor.setdefault(file_name, DefinitionFileNode(file_name, reldir/file_name))
         file_descriptor.set_source(file_entry) # Add the file entry to the descriptor
         
-    def pretty_print(self, indent: int = 0):
+    def parameters(self) -> set[str]:
+        """Returns the `$PARAM$` placeholders used in this node's subtree, without the `$` delimiters.
+        
+        Used for scripted effects/triggers/guis, e.g. `{ add_gold = $AMOUNT$ }` -> {"AMOUNT"}
+        """
+        params = set()
         for key, value in self.items():
-            print('    ' * indent + str(key) + ':', end=' ')
+            params.update(PARAMETER_PATTERN.findall(key))
             if isinstance(value, DefinitionValueNode):
-                print(str(value))
+                values = value.value if isinstance(value.value, list) else [value.value]
+                for val in values:
+                    params.update(PARAMETER_PATTERN.findall(str(val)))
+            else:
+                params.update(value.parameters())
+        return params
+        
+    def to_script(self, indent: int = 0) -> str:
+        """Serializes the children of this node back to Paradox script."""
+        lines = []
+        for key, value in self.items():
+            prefix = '    ' * indent
+            if isinstance(value, DefinitionValueNode):
+                if isinstance(value.value, list):
+                    lines.append(f"{prefix}{key} = {{ {' '.join(map(str, value.value))} }}")
+                elif value.value == key: # unnamed value inside a block
+                    lines.append(f"{prefix}{key}")
+                else:
+                    lines.append(f"{prefix}{key} = {value.value}")
+            elif len(value): # `bool(node)` checks name and rel_dir, not children
+                lines.append(f"{prefix}{key} = {{\n{value.to_script(indent + 1)}\n{prefix}}}")
+            else:
+                lines.append(f"{prefix}{key} = {{ }}")
+        return "\n".join(lines)
+        
+    def to_dict(self, include_meta: bool = False) -> Any:
+        """Converts this subtree to nested dicts for JSON export, value nodes become their value.
+        
+        With `include_meta`, every node is a dict with "__type__" and "rel_dir" keys, values are kept under "value".
+        """
+        if isinstance(self, DefinitionValueNode):
+            value = self.value
+            return {"__type__": self.type, "rel_dir": self.rel_dir.as_posix(), "value": value} if include_meta else value
+        result: dict[str, Any] = {"__type__": self.type, "rel_dir": self.rel_dir.as_posix()} if include_meta else {}
+        for key, child in self.items():
+            result[key] = child.to_dict(include_meta)
+        return result
+        
+    def dump(self, indent: int = 0) -> str:
+        """Returns a debug dump of this subtree with the type, name, value and sources of every node."""
+        sources = ", ".join(src.name or "?" for src in self.get_sources())
+        line = '    ' * indent + f"[{self.type}] {self.name}"
+        if isinstance(self, DefinitionValueNode):
+            line += f" = {self.value!r}"
+        if sources:
+            line += f" (sources: {sources})"
+        return "\n".join([line, *(child.dump(indent + 1) for child in self.values())])
+        
+    def export_graphviz(self, max_depth: int = -1) -> str:
+        """Exports this subtree in Graphviz DOT format, nodes are labeled `name:type`.
+        
+        Children deeper than `max_depth` levels below this node are left out, a negative `max_depth` means no limit.
+        """
+        lines = ["digraph definitions {"]
+        ids: dict[int, str] = {}
+        stack: list[tuple[DefinitionNode, int]] = [(self, 0)]
+        while stack:
+            node, depth = stack.pop()
+            node_id = ids.setdefault(id(node), f"n{len(ids)}")
+            label = f"{node.name}:{node.type}".replace("\\", "\\\\").replace('"', '\\"')
+            lines.append(f'    {node_id} [label="{label}"];')
+            if 0 <= max_depth <= depth:
+                continue
+            for child in node.values():
+                child_id = ids.setdefault(id(child), f"n{len(ids)}")
+                lines.append(f"    {node_id} -> {child_id};")
+            stack.extend((child, depth + 1) for child in reversed(node.values()))
+        lines.append("}")
+        return "\n".join(lines) + "\n"
+        
+    def format_tree(self, indent: int = 0) -> str:
+        """Returns the text printed by `pretty_print`: `key: value` for values, `key:` followed by the indented children otherwise."""
+        lines = []
+        for key, value in self.items():
+            prefix = '    ' * indent + str(key) + ':'
+            if isinstance(value, DefinitionValueNode):
+                lines.append(f"{prefix} {value}")
             elif isinstance(value, DefinitionNode):
-                print()
-                value.pretty_print(indent + 1)
+                lines.append(f"{prefix} ")
+                if len(value):
+                    lines.append(value.format_tree(indent + 1))
             else:
-                print(str(value))
+                lines.append(f"{prefix} {value}")
+        return "\n".join(lines)
+        
+    def pretty_print(self, indent: int = 0):
+        if text := self.format_tree(indent):
+            print(text)
                 
     def __repr__(self):
         return self.__class__.__name__ + f"(name={self.name}, rel_dir={self.rel_dir}, source={self.source})"
@@ -295,10 +580,10 @@ class DefinitionDirectoryNode(DefinitionNode):
         super().__init__(name, rel_dir, source=source, type='directory')
         
     def setdefault_by_dir(self, dirpath: str | Path, default: Optional[DefinitionNode] = None) -> DefinitionNode:
-        dirpath = Path(dirpath) 
+        parts = self._dir_parts(dirpath)
+        dirpath = Path(*parts)
         if default is None:
             default = DefinitionDirectoryNode(dirpath.name, dirpath)
-        parts = dirpath.parts
         current_level = self
         for part in parts[:-1]:
             current_level = current_level.setdefault(part, DefinitionDirectoryNode(part, current_level.rel_dir/part))
@@ -306,13 +591,73 @@ class DefinitionDirectoryNode(DefinitionNode):
 class DefinitionFileNode(DefinitionNode):
     def __init__(self, name:str, rel_dir:Path|str, source:Optional[SourceEntry] = None):
         super().__init__(name, rel_dir, source=source, type='file')
+        # formatting metadata of the source file, set when the file is parsed
+        self.line_ending: Optional[str] = None # "\n" or "\r\n"
+        self.indent_unit: Optional[str] = None # "\t" or a run of spaces
+        self.truncated: bool = False # whether definitions were dropped by the node budget or `MAX_NESTING`, max depth marks the identifiers
+        self.budget_exceeded: bool = False # whether parsing was cut short by the node budget, see `extract_node_definitions`
+        self.syntax_error: bool = False # whether tree-sitter reported errors, definitions are extracted anyway
+        self.parse_errors: list[tuple[int, int, int, int]] = [] # 0-based ranges of the syntax errors, see `error_ranges`
+        self.replaced_by: Optional[str] = None # name of the mod whose `replace_path` drops this file's definitions
+        self.encoding_issues: list[str] = [] # e.g. "missing UTF-8 BOM", see `find_encoding_issues`
+        self.duplicate_keys: list[tuple[str, int]] = [] # (key, line) of loc keys defined again later in the same file
+        self.from_cache: bool = False # whether the definitions were loaded from the parse cache instead of parsed
+class DefinitionVirtualNode(DefinitionNode):
+    def __init__(self, name:str, rel_dir:Path|str, source:Optional[SourceEntry] = None):
+        super().__init__(name, rel_dir, source=source, type='virtual')
 class DefinitionIdentifierNode(DefinitionNode):
     def __init__(self, name:str, rel_dir:Path|str, source:Optional[SourceEntry] = None):
         super().__init__(name, rel_dir, source=source, type='identifier')
+        self.truncated: bool = False # whether the parsing max depth dropped the contents of this block
 class DefinitionValueNode(DefinitionNode):
-    def __init__(self, name:str, rel_dir:Path|str, value: str|int|bool|None|list = None):
+    def __init__(self, name:str, rel_dir:Path|str, value: str|int|bool|None|list = None, kind: Optional[str] = None):
         super().__init__(name, rel_dir, type='value')
         self.value = value
+        # "scalar": `key = value`, "array": `key = { a b }`, "tagged_array": `key = hsv{ 0.5 0.5 0.5 }`
+        self.kind: str = kind or ('array' if isinstance(value, list) else 'scalar')
+    def value_kind(self) -> str:
+        return self.kind
     def __str__(self):
         return str(self.value)
-    
\ No newline at end of file
+def _read_source_bytes(file: Path) -> bytes:
+    stat = file.stat() # keyed on the file's stats, so an edited file is read again
+    return _read_source_bytes_cached(file, stat.st_mtime_ns, stat.st_size)
+
+@lru_cache(maxsize=16)
+def _read_source_bytes_cached(file: Path, mtime_ns: int, size: int) -> bytes:
+    return file.read_bytes()
+
+class DefinitionLazyValueNode(DefinitionValueNode):
+    """A scalar value node that keeps only the byte range of its value,\
+        reading it from the source file when `value` is first accessed.
+    """
+    def __init__(self, name:str, rel_dir:Path|str, file: Path, byte_range: tuple[int, int]):
+        self.file: Path = Path(file)
+        self.byte_range: tuple[int, int] = byte_range
+        super().__init__(name, rel_dir, kind='scalar')
+    @property
+    def value(self) -> Optional[str]:
+        if self._value is None:
+            start, end = self.byte_range
+            return _read_source_bytes(self.file)[start:end].decode('utf-8')
+        return self._value
+    @value.setter
+    def value(self, value: Optional[str]) -> None:
+        self._value = value # assigning a value makes the node eager
+class DefinitionLocValueNode(DefinitionValueNode):
+    def __init__(self, name:str, rel_dir:Path|str, value: Optional[str] = None, line: Optional[int] = None):
+        super().__init__(name, rel_dir, value=value)
+        self.line: Optional[int] = line # 1-based line of the key in its file
+        self.comment: Optional[str] = None # the `#` comment line right above the key, if attached by the parser
+        # (referenced key, column of the opening `$` within the value)
+        self.references: list[tuple[str, int]] = [
+            (match.group(1), match.start()) for match in LOC_REFERENCE_PATTERN.finditer(value or "")
+        ]
+
+NODE_TYPES: dict[str, type[DefinitionNode]] = {
+    'directory': DefinitionDirectoryNode,
+    'file': DefinitionFileNode,
+    'virtual': DefinitionVirtualNode,
+    'identifier': DefinitionIdentifierNode,
+    'value': DefinitionValueNode,
+}
\ No newline at end of file
@@ -30,13 +30,21 @@ def locate_mod_from_file(file_path: str | Path, mod_root: Path)-> Optional[Mod]:
             print("Mod descriptor not found:", e)
             return None
             
-def parse_paradox_mod_descriptor(text:str)-> dict[str, str|List[str]]:
+def parse_paradox_mod_descriptor(text:str)-> dict[str, str|List[str]|dict[str, str]]:
+    # game_custom_data holds unquoted values, cut it out so it doesn't swallow the following keys
+    custom_data = {}
+    m = re.search(r'game_custom_data\s*=\s*\{([^}]*)\}', text, re.S)
+    if m:
+        custom_data = dict(re.findall(r'([a-zA-Z0-9_]+)\s*=\s*"?([^"\s}]*)"?', m.group(1)))
+        text = text[:m.start()] + text[m.end():]
     result = dict(re.findall(r'([a-zA-Z0-9_]+)\s*=\s*"?([^"]*)"?', text))
     # capture tags list content inside braces and extract quoted strings
     m = re.search(r'tags\s*=\s*\{([^}]*)\}', text, re.S)
     result['tags'] = []
     if m:
         result['tags'] = re.findall(r'"([^"]+)"', m.group(1))
+    if custom_data:
+        result['game_custom_data'] = custom_data
     return result
 
 def load_mod_descriptor(path: Path | str) -> Mod:
@@ -110,6 +118,32 @@ def get_enabled_mod_descriptors(mod_list_path: Optional[str|Path]= None, pandora
         desc.load_order = i
         mod_descriptors.append(desc)
     return mod_descriptors
+def read_dlc_load(path: str|Path) -> List[str]:
+    """Returns the descriptor paths of `enabled_mods` in dlc_load.json, in load order. A missing file is created empty."""
+    path = Path(path)
+    if not path.exists():
+        write_dlc_load(path, [], [])
+        return []
+    with open(path, "r", encoding="utf-8") as f:
+        dlc_data = json.load(f)
+    return [str(p) for p in dlc_data.get("enabled_mods", [])]
+def write_dlc_load(path: str|Path, descriptor_paths: List[str], disabled_dlcs: Optional[List[str]] = None):
+    """Writes `descriptor_paths` as `enabled_mods` of dlc_load.json, other keys of an existing file are kept.
+    
+    `disabled_dlcs` replaces the file's list only when given, so writing the mods keeps the DLC choices.
+    """
+    path = Path(path)
+    dlc_data = {}
+    if path.exists():
+        with open(path, "r", encoding="utf-8") as f:
+            dlc_data = json.load(f)
+    dlc_data["enabled_mods"] = list(descriptor_paths)
+    if disabled_dlcs is not None:
+        dlc_data["disabled_dlcs"] = list(disabled_dlcs)
+    dlc_data.setdefault("disabled_dlcs", [])
+    path.parent.mkdir(parents=True, exist_ok=True)
+    with open(path, "w", encoding="utf-8") as f:
+        json.dump(dlc_data, f, ensure_ascii=False, indent=4)
 # ------- Paradox Mod Manager playset based functions -------
 def get_playset_mod_dirs(playset_dir: Path) -> List[Path]:
     mod_dirs = []
@@ -135,6 +169,17 @@ def get_playset_mod_descriptors(playset_dir: Path|str) -> List[Mod]:
                 desc.load_order = i
                 mod_descriptors.append(desc)
     return mod_descriptors
+# ------- JSON export/import of mod metadata -------
+def export_mods_json(mods: List[Mod], path: str|Path):
+    """Writes the full metadata of the mods to a JSON file, e.g. for backing up a playset."""
+    data = [mod.as_dict() for mod in mods]
+    with open(path, "w", encoding="utf-8") as f:
+        json.dump(data, f, ensure_ascii=False, indent=4)
+def import_mods_json(path: str|Path) -> List[Mod]:
+    """Reads mods written by `export_mods_json`."""
+    with open(path, "r", encoding="utf-8") as f:
+        data = json.load(f)
+    return [Mod.from_dict(entry) for entry in data]
 def file_search_recursive(root_dir, depth=0, max_depth=1):
     file_list = []
     if depth > max_depth:
@@ -1,18 +1,35 @@
 import re
 from mod_analyzer.encoding import detect_encoding
-from mod_analyzer.mod.mod_list import DefinitionIdentifierNode, DefinitionValueNode, DefinitionNode
+from mod_analyzer.mod.mod_list import DefinitionIdentifierNode, DefinitionValueNode, DefinitionLocValueNode, DefinitionNode
 
-def extract_definitions(txt, root:DefinitionNode|None=None) -> DefinitionNode:
+def extract_definitions(txt, root:DefinitionNode|None=None, attach_comments: bool = False) -> DefinitionNode:
+    """Extracts the `key: "value"` entries of a localization file.
+    
+    With `attach_comments`, a `# comment` line right above a key is kept as the node's `comment`, e.g. a translator note.
+    """
     lang_match = re.compile(r'(l_[A-Za-z_]+):$', re.MULTILINE).match(txt)
     lang = lang_match.group(1) if lang_match else 'unknown'
     root = root or DefinitionNode(lang, f'localization/{lang}')
     # Match lines like: "  key: "value"" across multiple lines
     # Use MULTILINE so ^/$ apply per-line and allow unicode values
     pattern = re.compile(r'^\s*(?P<key>[A-Za-z0-9_.-]+):\s*"(?P<value>.*)"\s*$', re.MULTILINE)
+    line, pos = 1, 0
     for match in pattern.finditer(txt):
         key = match.group('key')
         value = match.group('value')
-        root[key] = DefinitionValueNode(key, root.rel_dir, value=value)
+        line += txt.count('\n', pos, match.start('key'))
+        pos = match.start('key')
+        if key in root and isinstance(getattr(root, 'duplicate_keys', None), list):
+            root.duplicate_keys.append((key, line)) # the later definition wins, same as in game
+        node = root[key] = DefinitionLocValueNode(key, root.rel_dir, value=value, line=line)
+        line_start = txt.rfind('\n', 0, pos) + 1
+        end = match.end('value') + 1 # past the closing quote
+        node.start_point = (line - 1, pos - line_start)
+        node.end_point = (line - 1, end - line_start) # loc values never span lines
+        if attach_comments and line_start: # not on the first line
+            prev_line = txt[txt.rfind('\n', 0, line_start - 1) + 1:line_start - 1].strip()
+            if prev_line.startswith('#'):
+                node.comment = prev_line.lstrip('#').strip()
     return root
     
 if __name__ == "__main__":
@@ -2,10 +2,11 @@ from pathlib import Path
 import tree_sitter as ts
 import tree_sitter_paradox as tsp
 from mod_analyzer.encoding import detect_encoding
-from mod_analyzer.mod.mod_list import DefinitionIdentifierNode, DefinitionValueNode, DefinitionNode
+from mod_analyzer.mod.mod_list import DefinitionIdentifierNode, DefinitionValueNode, DefinitionLazyValueNode, DefinitionNode, DefinitionFileNode
 
 language = ts.Language(tsp.language())
 parser = ts.Parser(language)
+MAX_NESTING = 200 # deeper blocks are dropped like an exhausted node budget, well below Python's recursion limit
 
 def dict_pretty_print(d: dict, indent: int = 0):
     for key, value in d.items():
@@ -16,16 +17,100 @@ def dict_pretty_print(d: dict, indent: int = 0):
         else:
             print(str(value))
             
+def debug_parse_tree(source_code: str|bytes) -> str:
+    """Returns the tree-sitter s-expression of a Paradox script, for diagnosing parser issues."""
+    if isinstance(source_code, str):
+        source_code = source_code.encode('utf-8')
+    tree = parser.parse(source_code)
+    return str(tree.root_node)
+
+def error_ranges(ts_node: ts.Node) -> list[tuple[int, int, int, int]]:
+    """Returns (start_row, start_col, end_row, end_col) of the ERROR/MISSING nodes below `ts_node`, in source order."""
+    ranges = []
+    stack = [ts_node]
+    while stack:
+        node = stack.pop()
+        if node.is_error or node.is_missing:
+            ranges.append((*node.start_point, *node.end_point))
+        elif node.has_error: # only subtrees containing errors are visited
+            stack.extend(reversed(node.children))
+    return ranges
+
+def parse_errors(source_code: str|bytes) -> list[tuple[int, int, int, int]]:
+    """Parses a Paradox script and returns the 0-based (start_row, start_col, end_row, end_col) of every syntax error."""
+    if isinstance(source_code, str):
+        source_code = source_code.encode('utf-8')
+    tree = parser.parse(source_code)
+    return error_ranges(tree.root_node)
+
+def _ast_node(ts_node: ts.Node, field: str|None = None) -> dict:
+    ast = {"type": ts_node.type, "start": tuple(ts_node.start_point), "end": tuple(ts_node.end_point)}
+    if field:
+        ast["field"] = field # e.g. "key", "value", "tag"
+    children = [
+        _ast_node(child, ts_node.field_name_for_child(i))
+        for i, child in enumerate(ts_node.children) if child.is_named
+    ]
+    if children:
+        ast["children"] = children
+    else:
+        ast["text"] = (ts_node.text or b'').decode('utf-8')
+    return ast
+
+def parse_script_ast(source_code: str|bytes) -> dict:
+    """Parses a Paradox script into nested dicts mirroring the tree-sitter tree.
+    
+    Unlike the definition tree, blocks (`statement`/`map`), `array`s and `tagged_array`s stay distinct.\
+    Each node: {"type", "start": (row, col), "end": (row, col), "field"?, "children" | "text"}
+    """
+    if isinstance(source_code, str):
+        source_code = source_code.encode('utf-8')
+    tree = parser.parse(source_code)
+    return _ast_node(tree.root_node)
+
 def extract_array_vals(node: ts.Node) -> list:
     assert node.type in ('array', 'hex_array')
     return [
             (n.text or b'').decode('utf-8')
             for n in node.children if n.type in ('simple_value','number')]
 
-def extract_node_definitions(ts_node: ts.Node, root:DefinitionNode, max_depth:int= -1, _depth = 0) -> DefinitionNode:
+def extract_script_definitions(source_code: str|bytes, root_name: str = 'root', rel_dir: str|Path = './', max_depth:int= -1) -> DefinitionNode:
+    """Parses a Paradox script and extracts its definitions under a new root node.
+
+    A `root_name` with a file suffix (e.g. "00_traits.txt") creates a file node,\
+        so the tree is typed the same way as in `ModManager.define_table`.
+    """
+    if isinstance(source_code, str):
+        source_code = source_code.encode('utf-8')
+    tree = parser.parse(source_code)
+    return extract_node_definitions(tree.root_node, None, max_depth=max_depth, root_name=root_name, rel_dir=rel_dir)
+
+def extract_node_definitions(ts_node: ts.Node, root:DefinitionNode|None, max_depth:int= -1, _depth = 0, root_name: str = 'root', rel_dir: str|Path = './', _file_root: DefinitionNode|None = None, lazy_values: bool = False, node_budget: int = -1, _budget: list[int]|None = None) -> DefinitionNode:
+    """Extracts the definitions of a tree-sitter node into `root`.
+    
+    With `lazy_values`, scalar values only keep their byte range and are read back from\
+        the source file of `root` on access (see `DefinitionLazyValueNode`).
+    A non-negative `node_budget` caps the tree-sitter nodes visited for the file. Once exhausted (or past `MAX_NESTING`)\
+        the rest is skipped, the partial results are kept and the file root is marked `budget_exceeded`.
+    """
     if root is None:
-        root = DefinitionIdentifierNode('root', './', type='root')
+        if Path(root_name).suffix:
+            root = DefinitionFileNode(root_name, rel_dir)
+        else:
+            root = DefinitionNode(root_name, rel_dir, type='root')
+    _file_root = _file_root or root
+    _budget = _budget or [node_budget]
+    if getattr(_file_root, 'budget_exceeded', False):
+        return root
+    if _budget[0] == 0 or _depth > MAX_NESTING:
+        _file_root.budget_exceeded = True
+        _file_root.truncated = True # the rest of the file is dropped
+        return root
+    if _budget[0] > 0:
+        _budget[0] -= 1
     if max_depth >=0 and _depth > max_depth:
+        if ts_node.named_child_count: # real definitions are dropped, `trait_x = { }` is left unmarked
+            root.truncated = True
         return root
     rel_dir = root.rel_dir # rel_dir represents the source_file path, pass it down
     if ts_node.type in '{}':
@@ -35,12 +120,13 @@ def extract_node_definitions(ts_node: ts.Node, root:DefinitionNode, max_depth:in
             if child.type == 'simple_value': # this is an unnamed value inside a block
                 val = (child.text or b'').decode('utf-8')
                 root[val] = DefinitionValueNode(val, rel_dir, value=val)
+                root[val].start_point, root[val].end_point = tuple(child.start_point), tuple(child.end_point)
             else:
-                extract_node_definitions(child, root, max_depth, _depth)                    
+                extract_node_definitions(child, root, max_depth, _depth, _file_root=_file_root, lazy_values=lazy_values, _budget=_budget)
         return root
     elif ts_node.type in ('source_file','map'):
         for child in ts_node.children:
-            val = extract_node_definitions(child, root, max_depth, _depth)
+            val = extract_node_definitions(child, root, max_depth, _depth, _file_root=_file_root, lazy_values=lazy_values, _budget=_budget)
         return root
     
     elif ts_node.type in ('assignment', 'typed_assignment'): 
@@ -54,7 +140,9 @@ def extract_node_definitions(ts_node: ts.Node, root:DefinitionNode, max_depth:in
         if not (ts_key_node and ts_val_node):
             return root
         key = (ts_key_node.text or b'').decode('utf-8')
-        if ts_val_node.type =='simple_value': # ex: factor = 10
+        if ts_val_node.type =='simple_value' and lazy_values and _file_root.source is not None:
+            child = DefinitionLazyValueNode(key, rel_dir, file=_file_root.source.file, byte_range=(ts_val_node.start_byte, ts_val_node.end_byte))
+        elif ts_val_node.type =='simple_value': # ex: factor = 10
             child = DefinitionValueNode(key, rel_dir, value=(ts_val_node.text or b'').decode('utf-8'))
         elif ts_val_node.type =='array': # ex: key = { val1 val2 val3 }
             child = DefinitionValueNode(key, rel_dir, value=extract_array_vals(ts_val_node))
@@ -62,12 +150,13 @@ def extract_node_definitions(ts_node: ts.Node, root:DefinitionNode, max_depth:in
             tag_node = ts_val_node.child_by_field_name('tag')
             tag = (tag_node.text or b'').decode('utf-8') if tag_node else ''
             if _value_node:= ts_val_node.child_by_field_name('value'):
-                child = DefinitionValueNode(key, rel_dir, value=tag+"{"+", ".join(extract_array_vals(_value_node))+"}")
+                child = DefinitionValueNode(key, rel_dir, value=tag+"{"+", ".join(extract_array_vals(_value_node))+"}", kind='tagged_array')
             else: # empty tagged array
-                child = DefinitionValueNode(key, rel_dir, value=tag+"{}")
+                child = DefinitionValueNode(key, rel_dir, value=tag+"{}", kind='tagged_array')
         else: # nested block ('statement', 'map')
             child = DefinitionIdentifierNode(key, rel_dir, source=root.source)
-            val = extract_node_definitions(ts_val_node, child, max_depth, _depth+1)
+            val = extract_node_definitions(ts_val_node, child, max_depth, _depth+1, _file_root=_file_root, lazy_values=lazy_values, _budget=_budget)
+        child.start_point, child.end_point = tuple(ts_node.start_point), tuple(ts_node.end_point)
         root[key] = child
         return root
     return root
@@ -0,0 +1,74 @@
+"""
+Fixtures shared by the tests. Run the suite from `src` with `python -m unittest discover tests`.
+"""
+import tempfile
+import unittest
+from pathlib import Path
+
+from mod_analyzer.mod import ModManager, Mod, ModList, SourceEntry, DefinitionNode, DefinitionFileNode
+from mod_analyzer.mod.mod_list import DefinitionIdentifierNode, DefinitionValueNode
+from mod_analyzer.mod import paradox_loc_parser, paradox_parser
+
+def _parser_available() -> bool:
+    try:
+        paradox_parser.parser.parse(b"a = 1")
+    except Exception:
+        return False
+    return True
+
+PARSER_AVAILABLE = _parser_available() # script parsing needs the tree-sitter-paradox grammar
+
+def make_mod(name: str, load_order: int, enabled: bool = True, root: str|Path = "/mods", **kwargs) -> Mod:
+    return Mod(name=name, path=Path(root)/name, enabled=enabled, load_order=load_order, **kwargs)
+
+def make_manager(*mods: Mod, conflict_check_range: str|None = "all") -> ModManager:
+    manager = ModManager()
+    manager.conflict_check_range = conflict_check_range
+    manager.mod_list = ModList(list(mods), load_order=[mod.name for mod in mods]) # keeps each mod's own load_order
+    return manager
+
+def make_entry(mod: Mod, rel_path: str|Path) -> SourceEntry:
+    entry = SourceEntry(mod.path/rel_path)
+    entry.link_mod(mod)
+    return entry
+
+def build_definitions(node: DefinitionNode, tree: dict, rel_dir: Path, source: SourceEntry) -> DefinitionNode:
+    """Fills `node` from a nested dict, dicts become identifier nodes and other values value nodes."""
+    for key, value in tree.items():
+        if isinstance(value, dict):
+            node[key] = build_definitions(DefinitionIdentifierNode(key, rel_dir, source=source), value, rel_dir, source)
+        else:
+            node[key] = DefinitionValueNode(key, rel_dir, value=value)
+    return node
+
+def add_script_file(manager: ModManager, mod: Mod, rel_path: str|Path, tree: dict) -> DefinitionFileNode:
+    """Merges a script file into the manager as if it was parsed from `tree`, e.g. {"brave": {"category": "personality"}}."""
+    entry = make_entry(mod, rel_path)
+    file_node = DefinitionFileNode(entry.file.name, entry.rel_path.parent, source=entry)
+    build_definitions(file_node, tree, entry.rel_path.parent, entry)
+    manager.add_definition(entry, file_node)
+    return file_node
+
+def add_loc_file(manager: ModManager, mod: Mod, rel_path: str|Path, text: str) -> DefinitionFileNode:
+    """Merges a localization file parsed from `text` into the manager."""
+    entry = make_entry(mod, rel_path)
+    file_node = DefinitionFileNode(entry.file.name, entry.rel_path.parent, source=entry)
+    paradox_loc_parser.extract_definitions(text, file_node)
+    manager.add_definition(entry, file_node)
+    return file_node
+
+def write_files(root: Path, files: dict[str, str|bytes]) -> Path:
+    """Writes `files` (relative path -> content) under `root`, str content is written as UTF-8."""
+    for rel_path, content in files.items():
+        path = root/rel_path
+        path.parent.mkdir(parents=True, exist_ok=True)
+        if isinstance(content, str):
+            content = content.encode("utf-8")
+        path.write_bytes(content)
+    return root
+
+def make_temp_dir(test: unittest.TestCase) -> Path:
+    """Creates a directory removed when `test` finishes."""
+    tmp = tempfile.TemporaryDirectory()
+    test.addCleanup(tmp.cleanup)
+    return Path(tmp.name)
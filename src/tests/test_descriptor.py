@@ -0,0 +1,232 @@
+import hashlib
+import json
+import unittest
+from dataclasses import fields
+from pathlib import Path
+
+from mod_analyzer.mod import (
+    Mod, classify_outdated, find_near_duplicates, export_mods_json, import_mods_json,
+    resolve_load_order, find_dependency_cycles, assign_dup_ids, read_dlc_load, write_dlc_load,
+)
+from mod_analyzer.mod.descriptor import parse_descriptor, parse_descriptor_text
+from tests.helpers import make_mod, make_temp_dir, write_files
+
+class VerifyChecksumsTest(unittest.TestCase):
+    def test_wrong_hash_reported(self):
+        mod = make_mod("A", 0, root=make_temp_dir(self))
+        write_files(mod.path, {"common/traits/a.txt": "brave = { }\n", "events/a.txt": "namespace = a\n"})
+        good = hashlib.sha256(b"brave = { }\n").hexdigest()
+        write_files(mod.path, {"checksums.txt": f"common/traits/a.txt = {good}\nevents/a.txt = {'0' * 64}\n"})
+        self.assertEqual(mod.verify_checksums(mod.path/"checksums.txt"), [Path("events/a.txt")])
+
+class DescriptorTest(unittest.TestCase):
+    def test_archive_key(self):
+        root = make_temp_dir(self)
+        archive = (root/"workshop/123/mod.zip").as_posix()
+        write_files(root, {"descriptor.mod": f'name = "Zipped"\narchive = "{archive}"\nsupported_version = "1.12.*"\n'})
+        mod = Mod()
+        mod.load_from_descriptor(root/"descriptor.mod")
+        self.assertEqual(mod.name, "Zipped")
+        self.assertEqual(mod.path, Path(archive))
+        self.assertTrue(mod.is_archive)
+
+        mod.save_to_descriptor(root/"saved.mod")
+        saved = (root/"saved.mod").read_text(encoding="utf-8")
+        self.assertIn(f'archive = "{archive}"', saved)
+        self.assertNotIn("path =", saved)
+
+
+    def test_windows_paths_forward_slashed(self):
+        root = make_temp_dir(self)
+        mod = Mod(name="Win", path="C:\\Users\\me\\mod\\win", picture="gfx\\thumb.png", replace_path="common\\traits",
+                  replace_paths=["common\\traits", "history\\characters"], user_dir="saves\\win")
+        mod.save_to_descriptor(root/"win.mod")
+        saved = (root/"win.mod").read_text(encoding="utf-8")
+        self.assertNotIn("\\", saved)
+        for line in ('path = "C:/Users/me/mod/win"', 'picture = "gfx/thumb.png"', 'replace_path = "common/traits"',
+                     'replace_path = "history/characters"', 'user_dir = "saves/win"'):
+            self.assertIn(line, saved)
+        self.assertEqual(saved.count('replace_path = "common/traits"'), 1)
+
+
+    def test_launcher_v2_fields(self):
+        root = make_temp_dir(self)
+        write_files(root, {"descriptor.mod": 'name = "Modern"\npath = "mod/modern"\nuser_dir = "modern_saves"\n'
+                           'short_description = "A modern mod"\ngame_custom_data = {\n\tmultiplayer_synchronized = yes\n}\n'})
+        mod = Mod()
+        mod.load_from_descriptor(root/"descriptor.mod")
+        self.assertEqual(mod.user_dir, "modern_saves")
+        self.assertEqual(mod.short_description, "A modern mod")
+        self.assertEqual(mod.game_custom_data, {"multiplayer_synchronized": "yes"})
+
+        mod.save_to_descriptor(root/"saved.mod")
+        saved = Mod()
+        saved.load_from_descriptor(root/"saved.mod")
+        self.assertEqual((saved.user_dir, saved.short_description, saved.game_custom_data),
+                         (mod.user_dir, mod.short_description, mod.game_custom_data))
+
+class ModJsonTest(unittest.TestCase):
+    def make_full_mod(self) -> Mod:
+        mod = make_mod("Full", 3, tags=["Gameplay", "Fixes"], version="1.2", supported_version="1.12.*",
+                       remote_file_id="2887120253", picture="thumbnail.png", replace_path="common/traits",
+                       replace_paths=["common/traits", "events"], replaces=["Old"], dependencies=["Base"],
+                       file="/mods/full.mod", is_archive=True, root_dir="/mods", user_dir="full_user",
+                       short_description="Everything", game_custom_data={"multiplayer_synchronized": "yes"})
+        mod._dup_id = 2
+        return mod
+
+    def test_round_trip(self):
+        mod = self.make_full_mod()
+        path = make_temp_dir(self)/"mods.json"
+        export_mods_json([mod, make_mod("Plain", 4, enabled=False)], path)
+        loaded, plain = import_mods_json(path)
+        self.assertEqual(loaded.as_dict(), mod.as_dict())
+        self.assertEqual(loaded.dup_name, "Full#2")
+        self.assertEqual(loaded.replace_paths, [Path("common/traits"), Path("events")])
+        self.assertFalse(plain.enabled)
+        self.assertEqual(plain.load_order, 4)
+
+    def test_as_dict_formatting(self):
+        data = self.make_full_mod().as_dict()
+        self.assertEqual(data["tags"], ["Gameplay", "Fixes"])
+        self.assertEqual(data["replaces"], ["Old"])
+        self.assertEqual(data["dependencies"], ["Base"])
+        self.assertEqual(data["supported_version"], "1.12.*")
+        self.assertEqual(data["remote_file_id"], "2887120253")
+        self.assertEqual(data["picture"], "thumbnail.png")
+        self.assertEqual(data["replace_path"], str(Path("common/traits")))
+        self.assertEqual(data["file"], str(Path("/mods/full.mod")))
+        self.assertEqual(data["dup_id"], 2)
+        self.assertIsNone(make_mod("Plain", 0).as_dict()["file"])
+
+    def test_from_dict_coercion(self):
+        mod = Mod.from_dict({"name": "A", "load_order": "3", "enabled": "True", "path": "/mods/A", "unknown": 1})
+        self.assertEqual((mod.name, mod.load_order, mod.enabled, mod.path), ("A", 3, True, Path("/mods/A")))
+        self.assertFalse(Mod.from_dict({"enabled": "false"}).enabled)
+        default = Mod.from_dict({})
+        self.assertEqual((default.load_order, default.tags, default.supported_version, default.dup_name), (-1, [], None, ""))
+
+    def test_as_dict_has_every_field(self):
+        public = {f.name for f in fields(Mod) if not f.name.startswith("_")}
+        self.assertEqual(set(Mod().as_dict()), public | {"dup_id"})
+
+class OutdatedTest(unittest.TestCase):
+    def test_classify_outdated(self):
+        versions = {"Major": "0.9.*", "Minor": "1.11.*", "Patch": "1.12.3", "Current": "1.12.*", "Newer": "1.13.0", "Unset": None}
+        mods = [Mod(name=name, supported_version=version) for name, version in versions.items()]
+        self.assertEqual(classify_outdated(mods, "1.12.4"), [
+            ("Major", "major"), ("Minor", "minor"), ("Patch", "patch"), ("Current", "ok"), ("Newer", "ok"), ("Unset", "ok"),
+        ])
+
+    def test_is_outdated_wildcard(self):
+        cases = [
+            ("1.12.*", "1.12.5", False),
+            ("1.12.*", "1.13.0", True),
+            ("1.12.*", "1.11.9", False),
+            ("*", "1.13.0", False),
+            ("1.12", "1.12.5", False), # fewer components
+            ("1.12", "1.13", True),
+            ("1.12.4", "1.12.5", True),
+            (" 1.12.5", "1.12.5", False),
+        ]
+        for supported_version, current_version, outdated in cases:
+            with self.subTest(supported_version=supported_version, current_version=current_version):
+                self.assertEqual(Mod(supported_version=supported_version).is_outdated(current_version), outdated)
+        self.assertFalse(Mod().is_outdated("1.13.0"))
+
+class NearDuplicatesTest(unittest.TestCase):
+    def test_similar_names_flagged(self):
+        names = ["Community Flavor Pack", "Ethnicities and Portraits", "Community Flavour Pack", "Community-Flavor Pack!"]
+        mods = [Mod(name=name) for name in names]
+        pairs = find_near_duplicates(mods)
+        self.assertEqual([(a, b, round(similarity, 3)) for a, b, similarity in pairs], [
+            ("Community Flavor Pack", "Community-Flavor Pack!", 1.0),
+            ("Community Flavor Pack", "Community Flavour Pack", 0.955),
+            ("Community Flavour Pack", "Community-Flavor Pack!", 0.955),
+        ])
+        self.assertEqual(len(find_near_duplicates(mods, threshold=1.0)), 1)
+
+class ParseDescriptorTest(unittest.TestCase):
+    def test_crlf_and_comments(self):
+        text = '# generated by the launcher\r\nname = "Crlf" # trailing comment\r\ntags = {\r\n\t"Gameplay" # a tag\r\n\t"Fixes"\r\n}\r\n'
+        self.assertEqual(parse_descriptor_text(text), {"name": "Crlf", "tags": ["Gameplay", "Fixes"], "replaces": [], "dependencies": []})
+
+    def test_bom(self):
+        root = make_temp_dir(self)
+        write_files(root, {"descriptor.mod": b'\xef\xbb\xbfname = "Bom"\r\nversion = "1.0"\r\n'})
+        self.assertEqual(parse_descriptor(root/"descriptor.mod")["name"], "Bom")
+        self.assertEqual(parse_descriptor_text('\ufeffname = "Bom"')["name"], "Bom")
+
+    def test_malformed(self):
+        with self.assertRaises(ValueError):
+            parse_descriptor_text('name = "Broken"\ntags = {\n\t"Gameplay"\n')
+        with self.assertRaises(ValueError):
+            parse_descriptor_text('name')
+
+    def test_regex_fallback(self):
+        root = make_temp_dir(self)
+        write_files(root, {"descriptor.mod": f'name = "Broken"\npath = "{(root/"broken").as_posix()}"\ntags = {{\n\t"Gameplay"\n'})
+        mod = Mod()
+        with self.assertLogs("mod_analyzer", "WARNING"):
+            mod.load_from_descriptor(root/"descriptor.mod")
+        self.assertEqual(mod.name, "Broken")
+        self.assertEqual(mod.path, root/"broken")
+
+class ResolveLoadOrderTest(unittest.TestCase):
+    def test_dependencies_first_and_stable(self):
+        mods = [make_mod("A", 0, dependencies=["C"]), make_mod("B", 1), make_mod("C", 2), make_mod("D", 3)]
+        ordered = resolve_load_order(mods)
+        self.assertEqual([mod.name for mod in ordered], ["B", "C", "A", "D"])
+        self.assertEqual([mod.load_order for mod in ordered], [0, 1, 2, 3])
+
+    def test_missing_dependency_ignored(self):
+        mods = [make_mod("A", 5, dependencies=["Unknown", "A"]), make_mod("B", 2)]
+        ordered = resolve_load_order(mods)
+        self.assertEqual([(mod.name, mod.load_order) for mod in ordered], [("B", 0), ("A", 1)])
+
+    def test_cycle_members_named(self):
+        mods = [make_mod("A", 0, dependencies=["B"]), make_mod("B", 1, dependencies=["A"]),
+                make_mod("C", 2, dependencies=["A"]), make_mod("D", 3)]
+        with self.assertRaises(ValueError) as ctx:
+            resolve_load_order(mods)
+        self.assertEqual(str(ctx.exception), "Dependency cycle among mods: A, B") # C is only blocked by the cycle
+        self.assertEqual([mod.load_order for mod in mods], [0, 1, 2, 3]) # untouched on failure
+
+class DependencyCyclesTest(unittest.TestCase):
+    def test_self_loop_and_cycle(self):
+        mods = [make_mod("A", 0, dependencies=["B"]), make_mod("B", 1, dependencies=["C"]), make_mod("C", 2, dependencies=["A"]),
+                make_mod("D", 3, dependencies=["D"]), make_mod("E", 4, dependencies=["A", "Unknown"])]
+        self.assertEqual(find_dependency_cycles(mods), [["A", "B", "C"], ["D"]])
+
+    def test_long_chain(self):
+        count = 5000 # deeper than the recursion limit
+        mods = [make_mod(f"M{i}", i, dependencies=[f"M{i+1}"] if i + 1 < count else []) for i in range(count)]
+        self.assertEqual(find_dependency_cycles(mods), [])
+        mods[-1].dependencies = ["M0"]
+        self.assertEqual(find_dependency_cycles(mods), [[mod.name for mod in mods]])
+
+class AssignDupIdsTest(unittest.TestCase):
+    def test_numbered_by_load_order(self):
+        mods = [make_mod("Name", 2), make_mod("Other", 1), make_mod("Name", 0), make_mod("Name", 3), make_mod("Single", 4)]
+        assign_dup_ids(mods)
+        self.assertEqual([mod.dup_name for mod in mods], ["Name#1", "Other", "Name", "Name#2", "Single"])
+        self.assertEqual([mod._dup_id for mod in mods], [1, 0, 0, 2, 0])
+
+class DlcLoadTest(unittest.TestCase):
+    def test_missing_file_created(self):
+        path = make_temp_dir(self)/"game"/"dlc_load.json"
+        self.assertEqual(read_dlc_load(path), [])
+        self.assertEqual(json.loads(path.read_text(encoding="utf-8")), {"enabled_mods": [], "disabled_dlcs": []})
+
+    def test_write_keeps_other_keys(self):
+        path = make_temp_dir(self)/"dlc_load.json"
+        path.write_text(json.dumps({"enabled_mods": ["mod/old.mod"], "disabled_dlcs": ["dlc/dlc001.dlc"], "other": 1}), encoding="utf-8")
+        write_dlc_load(path, ["mod/a.mod", "mod/b.mod"])
+        self.assertEqual(read_dlc_load(path), ["mod/a.mod", "mod/b.mod"])
+        self.assertEqual(json.loads(path.read_text(encoding="utf-8")),
+                         {"enabled_mods": ["mod/a.mod", "mod/b.mod"], "disabled_dlcs": ["dlc/dlc001.dlc"], "other": 1})
+        write_dlc_load(path, [], disabled_dlcs=[])
+        self.assertEqual(json.loads(path.read_text(encoding="utf-8"))["disabled_dlcs"], [])
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,47 @@
+import unittest
+
+from mod_analyzer.encoding import detect_line_format, find_encoding_issues
+from mod_analyzer.mod import ModManager
+from tests.helpers import make_mod, make_manager, make_entry, make_temp_dir, write_files
+
+class LineFormatTest(unittest.TestCase):
+    def test_crlf_with_tabs(self):
+        source = b"brave = {\r\n\tcategory = personality\r\n\tflag = {\r\n\t\tyes = yes\r\n\t}\r\n}\r\n"
+        self.assertEqual(detect_line_format(source), ("\r\n", "\t"))
+
+    def test_lf_with_spaces(self):
+        self.assertEqual(detect_line_format(b"a = {\n    b = 1\n        c = 2\n}\n"), ("\n", "    "))
+
+    def test_recorded_on_file_node(self):
+        root = make_temp_dir(self)
+        mod = make_mod("A", 0, root=root)
+        rel_path = "localization/english/a_l_english.yml"
+        write_files(mod.path, {rel_path: b'\xef\xbb\xbfl_english:\r\n\tkey_a: "A"\r\n'})
+        _, file_node, err = ModManager._extract_file_definitions(make_entry(mod, rel_path))
+        self.assertIsNone(err)
+        self.assertEqual((file_node.line_ending, file_node.indent_unit), ("\r\n", "\t"))
+        self.assertEqual(file_node["key_a"].value, "A")
+
+class EncodingIssuesTest(unittest.TestCase):
+    def test_bom_and_latin1_reported(self):
+        mod = make_mod("A", 0, root=make_temp_dir(self))
+        write_files(mod.path, {
+            "common/traits/bom.txt": b"\xef\xbb\xbfbrave = { }\n",
+            "common/traits/latin.txt": b"craven = { name = \"L\xe9on\" }\n",
+            "common/traits/clean.txt": b"lustful = { }\n",
+        })
+        manager = make_manager(mod, conflict_check_range=None)
+        manager.build_file_tree()
+        self.assertEqual(sorted(manager.encoding_issues()), [
+            (mod.path/"common/traits/bom.txt", "unexpected UTF-8 BOM"),
+            (mod.path/"common/traits/latin.txt", "non UTF-8 bytes at offset 20"),
+        ])
+
+    def test_find_encoding_issues(self):
+        self.assertEqual(find_encoding_issues(b"l_english:\n", ".yml"), ["missing UTF-8 BOM"])
+        self.assertEqual(find_encoding_issues(b"\xef\xbb\xbfl_english:\n", ".yml"), [])
+        self.assertEqual(find_encoding_issues(b"\xef\xbb\xbfwindow = { }\n", ".gui"), ["unexpected UTF-8 BOM"])
+        self.assertEqual(find_encoding_issues(b"a = 1\n\xef\xbb\xbfb = 2\n", ".txt"), ["UTF-8 BOM inside the content"])
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,95 @@
+import sqlite3
+import unittest
+from pathlib import Path
+
+from mod_analyzer.mod.launcher_db import read_playsets, write_playset
+from tests.helpers import make_temp_dir
+
+# the tables of launcher-v2.sqlite used by `launcher_db`, with the launcher's NOT NULL constraints
+LAUNCHER_SCHEMA = """
+CREATE TABLE playsets (
+    id CHAR(36) PRIMARY KEY, name VARCHAR(255) NOT NULL, isActive BOOLEAN, loadOrder VARCHAR(255),
+    pdxId VARCHAR(255), pdxUserId VARCHAR(255), createdOn DATETIME NOT NULL, updatedOn DATETIME, syncedOn DATETIME,
+    lastServerChecksum VARCHAR(255), isRemoved BOOLEAN NOT NULL DEFAULT 0,
+    hasNotApprovedChanges BOOLEAN NOT NULL DEFAULT 0, syncState VARCHAR(255)
+);
+CREATE TABLE mods (id CHAR(36) PRIMARY KEY, displayName VARCHAR(255), dirPath VARCHAR(255), status VARCHAR(255));
+CREATE TABLE playsets_mods (
+    playsetId CHAR(36) NOT NULL REFERENCES playsets(id), modId CHAR(36) NOT NULL REFERENCES mods(id),
+    enabled BOOLEAN DEFAULT 1, position INTEGER, PRIMARY KEY (playsetId, modId)
+);
+"""
+
+def make_launcher_db(root: Path, mods: list[str], playsets: dict[str, list[tuple[str, int, bool]]], active: str = "") -> Path:
+    """Creates a launcher database with `mods` registered and `playsets` (name -> [(mod name, position, enabled)])."""
+    db_path = root/"launcher-v2.sqlite"
+    conn = sqlite3.connect(db_path)
+    with conn:
+        conn.executescript(LAUNCHER_SCHEMA)
+        conn.executemany("INSERT INTO mods (id, displayName) VALUES (?, ?)", [(f"mod-{name}", name) for name in mods])
+        for name, entries in playsets.items():
+            conn.execute(
+                "INSERT INTO playsets (id, name, isActive, createdOn) VALUES (?, ?, ?, '2024-01-01 00:00:00.000 +00:00')",
+                (f"playset-{name}", name, int(name == active)),
+            )
+            conn.executemany(
+                "INSERT INTO playsets_mods (playsetId, modId, position, enabled) VALUES (?, ?, ?, ?)",
+                [(f"playset-{name}", f"mod-{mod_name}", position, int(enabled)) for mod_name, position, enabled in entries],
+            )
+    conn.close()
+    return db_path
+
+class ReadPlaysetsTest(unittest.TestCase):
+    def test_ordered_by_position(self):
+        db_path = make_launcher_db(make_temp_dir(self), ["A", "B", "C"], {
+            "Main": [("C", 2, True), ("A", 0, True), ("B", 1, False)],
+            "Alt": [("B", 0, True)],
+        }, active="Main")
+        self.assertEqual(read_playsets(db_path), [
+            ("Alt", False, [("B", 0, True)]),
+            ("Main", True, [("A", 0, True), ("B", 1, False), ("C", 2, True)]),
+        ])
+
+    def test_missing_db_not_created(self):
+        db_path = make_temp_dir(self)/"launcher-v2.sqlite"
+        with self.assertRaises(sqlite3.OperationalError):
+            read_playsets(db_path)
+        self.assertFalse(db_path.exists())
+
+class WritePlaysetTest(unittest.TestCase):
+    def setUp(self):
+        self.db_path = make_launcher_db(make_temp_dir(self), ["A", "B", "C"], {"Main": [("A", 0, True), ("B", 1, True)]})
+
+    def playset_rows(self) -> list[tuple]:
+        conn = sqlite3.connect(self.db_path)
+        try:
+            return conn.execute("SELECT id, name, createdOn FROM playsets ORDER BY name").fetchall()
+        finally:
+            conn.close()
+
+    def test_create(self):
+        write_playset(self.db_path, "Compatch", [("C", 0, True), ("A", 1, False)])
+        self.assertEqual(read_playsets(self.db_path)[0], ("Compatch", False, [("C", 0, True), ("A", 1, False)]))
+        self.assertIsNotNone(self.playset_rows()[0][2]) # createdOn is NOT NULL in the launcher
+
+    def test_update_in_place(self):
+        before = self.playset_rows()
+        write_playset(self.db_path, "Main", [("B", 0, False), ("C", 1, True)])
+        self.assertEqual(read_playsets(self.db_path), [("Main", False, [("B", 0, False), ("C", 1, True)])])
+        self.assertEqual(self.playset_rows(), before) # same id, no second playset
+
+    def test_unknown_mod_rolls_back(self):
+        with self.assertRaises(OSError):
+            write_playset(self.db_path, "Main", [("C", 0, True), ("Unknown", 1, True)])
+        with self.assertRaises(OSError):
+            write_playset(self.db_path, "New", [("Unknown", 0, True)])
+        self.assertEqual(read_playsets(self.db_path), [("Main", False, [("A", 0, True), ("B", 1, True)])])
+
+    def test_missing_db_not_created(self):
+        db_path = self.db_path.parent/"launcher-v3.sqlite" # a wrong path in an existing folder
+        with self.assertRaises(OSError):
+            write_playset(db_path, "Main", [])
+        self.assertFalse(db_path.exists())
+
+if __name__ == "__main__":
+    unittest.main()
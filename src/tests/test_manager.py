@@ -0,0 +1,932 @@
+import threading
+import unittest
+from pathlib import Path
+
+from mod_analyzer.mod import ModManager, ModManagerConfig, ModList, ExtractionCancelled
+from mod_analyzer.mod.conflict import non_conflict_keywords
+from tests.helpers import PARSER_AVAILABLE, make_mod, make_manager, make_entry, make_temp_dir, add_script_file, add_loc_file, write_files
+
+TRAITS = "common/traits/00_traits.txt"
+
+class RecomputeConflictsTest(unittest.TestCase):
+    def test_exempt_keyword_change(self):
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 1)
+        manager = make_manager(mod_a, mod_b)
+        add_script_file(manager, mod_a, TRAITS, {"brave": {"category": "personality"}, "craven": {}})
+        add_script_file(manager, mod_b, TRAITS, {"brave": {"category": "fame"}})
+        manager.recompute_conflicts()
+        self.assertEqual(list(manager.conflict_issues), [("common/traits", "brave")])
+
+        non_conflict_keywords.add("brave")
+        self.addCleanup(non_conflict_keywords.discard, "brave")
+        manager.recompute_conflicts()
+        self.assertEqual(manager.conflict_issues, {})
+
+        non_conflict_keywords.discard("brave")
+        manager.recompute_conflicts()
+        self.assertEqual(list(manager.conflict_issues), [("common/traits", "brave")])
+
+
+    def test_script_toggle_without_reextraction(self):
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 1)
+        manager = make_manager(mod_a, mod_b)
+        manager.check_script_conflicts = False
+        add_script_file(manager, mod_a, TRAITS, {"brave": {"category": "personality"}})
+        add_script_file(manager, mod_b, TRAITS, {"brave": {"category": "fame"}})
+        add_loc_file(manager, mod_a, "localization/english/a_l_english.yml", 'l_english:\n brave: "Brave"\n')
+        add_loc_file(manager, mod_b, "localization/english/b_l_english.yml", 'l_english:\n brave: "Bold"\n')
+        self.assertEqual([(node.rel_dir.as_posix(), node.name) for node in manager.conflict_identifiers], [("localization/english", "brave")])
+        self.assertEqual(sorted(manager.identifier_index["common/traits"]["brave"].sources), ["A", "B"])
+
+        manager.check_script_conflicts = True
+        manager.recompute_conflicts()
+        self.assertEqual(sorted(manager.conflict_issues), [("common/traits", "brave"), ("localization/english", "brave")])
+
+        manager.check_script_conflicts = False
+        manager.recompute_conflicts()
+        self.assertEqual(list(manager.conflict_issues), [("localization/english", "brave")])
+
+class SuggestResolutionsTest(unittest.TestCase):
+    def test_compatch_suggested(self):
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 2)
+        patch = make_mod("A_B_patch", 1, dependencies=["A", "B"]) # loads before B, B alone would win
+        manager = make_manager(mod_a, mod_b, patch)
+        for mod in (mod_a, mod_b, patch):
+            add_script_file(manager, mod, TRAITS, {"brave": {"category": mod.name}})
+        manager.recompute_conflicts()
+        self.assertEqual(manager.suggest_resolutions(), [{
+            "path": "common/traits::brave",
+            "winner": "A_B_patch",
+            "losers": ["A", "B"],
+            "resolution": "compatch",
+        }])
+
+class IdentifierIndexTest(unittest.TestCase):
+    def test_identifiers_in(self):
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 1)
+        manager = make_manager(mod_a, mod_b)
+        add_script_file(manager, mod_a, TRAITS, {"brave": {}, "craven": {}})
+        add_script_file(manager, mod_b, "common/traits/b_traits.txt", {"brave": {}, "lustful": {}})
+        add_script_file(manager, mod_b, "common/decisions/b_decisions.txt", {"my_decision": {}})
+        names = sorted(node.name for node in manager.identifiers_in("common/traits"))
+        self.assertEqual(names, ["brave", "craven", "lustful"])
+        self.assertEqual(manager.identifiers_in("common/buildings"), [])
+
+class DependentsTest(unittest.TestCase):
+    def test_reference_to_other_mods_trait(self):
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 1)
+        manager = make_manager(mod_a, mod_b)
+        add_script_file(manager, mod_a, TRAITS, {"my_trait": {"category": "personality"}})
+        add_script_file(manager, mod_b, "common/traits/b_traits.txt", {"b_trait": {"opposites": ["my_trait"]}})
+        self.assertEqual(manager.dependents_of("A"), [("B", Path("common/traits/b_trait/opposites"))])
+        self.assertEqual(manager.dependents_of("B"), [])
+
+class ScriptedSignaturesTest(unittest.TestCase):
+    def test_parameter_captured(self):
+        mod_a = make_mod("A", 0)
+        manager = make_manager(mod_a)
+        add_script_file(manager, mod_a, "common/scripted_effects/a_effects.txt", {
+            "my_effect": {"scope:actor": {"add_opinion": {"target": "$TARGET$", "modifier": "friendly_opinion"}}},
+            "no_param_effect": {"add_gold": "10"},
+        })
+        self.assertEqual(manager.scripted_signatures(), {"my_effect": {"TARGET"}, "no_param_effect": set()})
+
+class GenerateCompatchTest(unittest.TestCase):
+    def test_two_mod_skeleton(self):
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 1)
+        manager = make_manager(mod_a, mod_b)
+        add_script_file(manager, mod_a, TRAITS, {"brave": {"category": "personality", "prowess": "2"}})
+        add_script_file(manager, mod_b, TRAITS, {"brave": {"category": "fame"}})
+        manager.recompute_conflicts()
+        self.assertEqual(manager.generate_compatch("common/traits/brave"), "\n".join([
+            "# Compatch skeleton for common/traits::brave",
+            "# Winner: B",
+            "# Overridden: A",
+            "brave = {",
+            "    category = fame",
+            "}",
+            "# --- A ---",
+            "# brave = {",
+            "#     category = personality",
+            "#     prowess = 2",
+            "# }",
+        ]) + "\n")
+
+    def test_unknown_conflict(self):
+        with self.assertRaises(KeyError):
+            make_manager().generate_compatch("common/traits/brave")
+
+class BuildFileTreeTest(unittest.TestCase):
+    def setUp(self):
+        root = make_temp_dir(self)
+        self.mods = [make_mod("A", 0, root=root), make_mod("B", 1, enabled=False, root=root)]
+        for mod in self.mods:
+            write_files(mod.path, {f"localization/english/{mod.name}_l_english.yml": f'\ufeffl_english:\n {mod.name}_key: "x"\n'})
+
+    def build(self, **kwargs) -> ModManager:
+        manager = make_manager(*self.mods, conflict_check_range=None)
+        manager.build_file_tree(**kwargs)
+        return manager
+
+    def test_disabled_mods_skipped(self):
+        manager = self.build()
+        self.assertEqual(manager.built_mods, ["A"])
+        self.assertEqual(sorted(manager.parsed_files), ["A"])
+
+    def test_include_disabled(self):
+        self.assertEqual(sorted(self.build(include_disabled=True).parsed_files), ["A", "B"])
+        self.assertEqual(sorted(self.build(file_range="all").parsed_files), ["A", "B"])
+        self.assertEqual(sorted(self.build(file_range="enabled", include_disabled=True).parsed_files), ["A"])
+
+    def test_progress(self):
+        write_files(self.mods[0].path, {"gfx/interface/icons/a_icon.dds": b"DDS "})
+        calls = []
+        manager = self.build(file_range="all", progress=lambda done, total, stage: calls.append((done, total, stage)))
+        self.assertEqual(calls, [(1, 2, "collect"), (2, 2, "collect"), (1, 1, "other"), (1, 2, "yml"), (2, 2, "yml")])
+        self.assertEqual((manager.extraction_stats["files"], manager.extraction_stats["parsed_files"]), (3, 2))
+
+    def test_rebuild_after_cancel(self):
+        manager = make_manager(*self.mods, conflict_check_range=None)
+        cancel = threading.Event()
+        def progress(done: int, total: int, stage: str):
+            if stage == "yml" and done == 1: # one loc file merged, the other one not yet
+                cancel.set()
+        with self.assertRaises(ExtractionCancelled):
+            manager.build_file_tree(file_range="all", progress=progress, cancel=cancel)
+        manager.build_file_tree(file_range="all")
+        clean = self.build(file_range="all")
+        self.assertEqual(manager.built_mods, clean.built_mods)
+        self.assertEqual({name: len(nodes) for name, nodes in manager.parsed_files.items()},
+                         {name: len(nodes) for name, nodes in clean.parsed_files.items()})
+        self.assertEqual(sorted(manager.identifier_index["localization"]), sorted(clean.identifier_index["localization"]))
+        self.assertEqual(manager.extraction_stats["nodes"], clean.extraction_stats["nodes"])
+
+class LocMapTest(unittest.TestCase):
+    def test_overridden_key_returns_winner(self):
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 1)
+        manager = make_manager(mod_a, mod_b)
+        add_loc_file(manager, mod_a, "localization/english/a_l_english.yml", 'l_english:\n brave: "Brave"\n craven: "Craven"\n')
+        add_loc_file(manager, mod_b, "localization/english/b_l_english.yml", 'l_english:\n brave: "Bold"\n')
+        self.assertEqual(manager.loc_map(), {"brave": "Bold", "craven": "Craven"})
+        self.assertEqual(manager.loc_map("french"), {})
+
+class VirtualNodeTest(unittest.TestCase):
+    def test_def_node_is_virtual(self):
+        mod_a = make_mod("A", 0)
+        manager = make_manager(mod_a)
+        add_script_file(manager, mod_a, TRAITS, {"brave": {}})
+        traits = manager.define_table.get_by_dir("common/traits")
+        self.assertFalse(traits.is_virtual())
+        self.assertTrue(traits["<def>"].is_virtual())
+        self.assertTrue(traits["<def>"].is_synthetic_name())
+        self.assertFalse(traits["00_traits.txt"].is_virtual())
+
+class ConflictGraphTest(unittest.TestCase):
+    def test_dot_edge_weight(self):
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 1)
+        manager = make_manager(mod_a, mod_b)
+        add_script_file(manager, mod_a, TRAITS, {"brave": {}, "craven": {}, "lustful": {}})
+        add_script_file(manager, mod_b, TRAITS, {"brave": {}, "craven": {}})
+        manager.recompute_conflicts()
+        self.assertEqual(manager.conflict_pairs(), {("A", "B"): 2})
+        self.assertIn('"A" -- "B" [label="2", weight=2];', manager.export_conflict_dot())
+
+class UnlocalizedIdentifiersTest(unittest.TestCase):
+    def test_missing_loc_key(self):
+        mod_a = make_mod("A", 0)
+        manager = make_manager(mod_a)
+        add_script_file(manager, mod_a, TRAITS, {"brave": {}, "craven": {}})
+        add_loc_file(manager, mod_a, "localization/english/a_l_english.yml", 'l_english:\n brave: "Brave"\n')
+        self.assertEqual(manager.unlocalized_identifiers(["common/traits"]), [("craven", "A")])
+
+class FileTreeDepthTest(unittest.TestCase):
+    def test_directories_stop_at_depth(self):
+        mod_a = make_mod("A", 0)
+        manager = make_manager(mod_a)
+        manager.file_tree_depth = 1
+        add_script_file(manager, mod_a, "common/traits/00_traits.txt", {"brave": {}})
+        common = manager.define_table["common"]
+        self.assertEqual(sorted(common.keys()), ["traits/00_traits.txt", "traits/<def>"])
+        self.assertEqual(common["traits/00_traits.txt"].type, "file")
+        self.assertEqual([node.name for node in manager.identifiers_in("common/traits")], ["brave"])
+
+class HighestPriorityTest(unittest.TestCase):
+    def test_winner_among_three(self):
+        manager = make_manager(make_mod("A", 0), make_mod("B", 2), make_mod("C", 1))
+        self.assertEqual(manager.highest_priority(["A", "B", "C"]), "B")
+        self.assertEqual(manager.highest_priority(["C", "A", "unknown"]), "C")
+        self.assertIsNone(manager.highest_priority(["unknown"]))
+
+
+    def test_override_reevaluates_replace_path(self):
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 1, replace_path=Path("common/traits"))
+        manager = make_manager(mod_a, mod_b)
+        a_traits = add_script_file(manager, mod_a, TRAITS, {"brave": {}, "craven": {}})
+        add_script_file(manager, mod_b, "common/traits/b_traits.txt", {"brave": {}})
+        manager.recompute_conflicts()
+        self.assertEqual(a_traits.replaced_by, "B")
+
+        manager.set_priority_override("A", 5) # A now outranks B, so B's replace_path no longer drops it
+        self.assertIsNone(a_traits.replaced_by)
+        self.assertEqual(sorted(manager.identifier_index["common/traits"]), ["brave", "craven"])
+        self.assertEqual(manager.conflict_summary()["common/traits::brave"]["winner"], "A")
+
+        manager.clear_priority_override("A")
+        self.assertEqual(a_traits.replaced_by, "B")
+        self.assertEqual(sorted(manager.identifier_index["common/traits"]), ["brave"])
+        self.assertEqual(manager.conflict_issues, {})
+
+    def test_override_owner_of_disabled_sources(self):
+        mod_a, mod_b = make_mod("A", 0, enabled=False), make_mod("B", 1, enabled=False)
+        manager = make_manager(mod_a, mod_b)
+        add_script_file(manager, mod_a, TRAITS, {"brave": {}})
+        add_script_file(manager, mod_b, "common/traits/b_traits.txt", {"brave": {}})
+        self.assertEqual(manager.unlocalized_identifiers(["common/traits"]), [("brave", "B")])
+        manager.set_priority_override("A", 5)
+        self.assertEqual(manager.unlocalized_identifiers(["common/traits"]), [("brave", "A")])
+
+
+    def test_override_flips_winner(self):
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 1)
+        manager = make_manager(mod_a, mod_b)
+        add_script_file(manager, mod_a, TRAITS, {"brave": {"ai_boldness": "10"}})
+        add_script_file(manager, mod_b, "common/traits/b_traits.txt", {"brave": {"ai_boldness": "50"}})
+        add_loc_file(manager, mod_a, "localization/english/a_l_english.yml", 'l_english:\n brave: "Brave A"\n')
+        add_loc_file(manager, mod_b, "localization/english/b_l_english.yml", 'l_english:\n brave: "Brave B"\n')
+        manager.recompute_conflicts()
+        self.assertEqual(manager.conflict_summary()["common/traits::brave"]["winner"], "B")
+
+        manager.set_priority_override("A", 5)
+        self.assertEqual(manager.get_priority_override("A"), 5)
+        self.assertEqual(manager.conflict_summary()["common/traits::brave"]["winner"], "A")
+        self.assertEqual(manager.effective_value("common/traits/brave/ai_boldness"), ("10", "A"))
+        self.assertEqual(manager.loc_map(), {"brave": "Brave A"})
+        self.assertEqual(manager.highest_priority(["A", "B"]), "A")
+        self.assertEqual([mod.load_order for mod in (mod_a, mod_b)], [0, 1]) # the launch order is unchanged
+
+        manager.clear_priority_override("A")
+        self.assertIsNone(manager.get_priority_override("A"))
+        self.assertEqual(manager.loc_map(), {"brave": "Brave B"})
+
+class BrokenFileRefsTest(unittest.TestCase):
+    def test_missing_dds_reported(self):
+        root = make_temp_dir(self)
+        mod_a = make_mod("A", 0, root=root)
+        write_files(mod_a.path, {"gfx/interface/icons/traits/brave.dds": b"DDS "})
+        manager = make_manager(mod_a)
+        manager.GAME_DIR = root/"game"
+        add_script_file(manager, mod_a, TRAITS, {
+            "brave": {"icon": "gfx/interface/icons/traits/brave.dds"},
+            "craven": {"icon": '"gfx/interface/icons/traits/craven.dds"'},
+        })
+        self.assertEqual(manager.broken_file_refs(), [("gfx/interface/icons/traits/craven.dds", "A")])
+
+class ConfigTest(unittest.TestCase):
+    def setUp(self):
+        root = make_temp_dir(self)
+        self.mods = [make_mod("A", 0, root=root), make_mod("B", 1, root=root)]
+        for mod in self.mods:
+            write_files(mod.path, {
+                f"localization/french/{mod.name}_l_french.yml": f'\ufeffl_french:\n # note {mod.name}\n brave: "Brave {mod.name}"\n',
+                f"common/traits/{mod.name}_traits.txt": "brave = {\n\tflag = {\n\t\tyes = yes\n\t}\n}\n",
+            })
+
+    def make(self, config: ModManagerConfig) -> ModManager:
+        manager = ModManager.with_config(config)
+        manager.mod_list = ModList(self.mods, load_order=[mod.name for mod in self.mods])
+        manager.build()
+        return manager
+
+    def test_options_applied(self):
+        manager = self.make(ModManagerConfig(conflict_check_range="all", language="french", loc_comments=True))
+        self.assertEqual(manager.loc_map(), {"brave": "Brave B"})
+        self.assertIn(("localization/french", "brave"), manager.conflict_issues)
+        loc_node = next(node for node in manager.parsed_files["B"] if node.name.endswith(".yml"))
+        self.assertEqual(loc_node["brave"].comment, "note B")
+
+    def test_options_kept_per_manager(self):
+        with_comments = ModManager.with_config(ModManagerConfig(loc_comments=True, max_def_depth=2))
+        default = ModManager()
+        self.assertTrue(with_comments.loc_comments)
+        self.assertFalse(default.loc_comments)
+        self.assertEqual(default.max_def_depth, 0)
+
+    @unittest.skipUnless(PARSER_AVAILABLE, "tree-sitter-paradox grammar not available")
+    def test_max_def_depth(self):
+        shallow = self.make(ModManagerConfig(conflict_check_range="all"))
+        deep = self.make(ModManagerConfig(conflict_check_range="all", max_def_depth=2))
+        self.assertEqual(len(shallow.get_mod_definition("A", "common/traits", "brave")), 0)
+        self.assertEqual(deep.get_mod_definition("A", "common/traits", "brave")["flag"]["yes"].value, "yes")
+
+class LanguagesTest(unittest.TestCase):
+    def test_conflicts_within_language(self):
+        root = make_temp_dir(self)
+        mods = [make_mod("A", 0, root=root), make_mod("B", 1, root=root)]
+        loc = {
+            ("A", "english"): 'brave: "Brave"', ("B", "english"): 'brave: "Bold"',
+            ("A", "french"): 'craven: "Lâche"', ("B", "french"): 'brave: "Courageux"',
+        }
+        for (name, language), text in loc.items():
+            write_files(root/name, {f"localization/{language}/{name}_l_{language}.yml": f'\ufeffl_{language}:\n {text}\n'})
+        manager = ModManager.with_config(ModManagerConfig(conflict_check_range="all", language=["english", "french"]))
+        manager.mod_list = ModList(mods, load_order=["A", "B"])
+        manager.build()
+        self.assertEqual(list(manager.conflict_issues), [("localization/english", "brave")]) # B's french brave is alone
+        self.assertEqual(sorted(manager.identifier_index["localization"]), ["brave"])
+        self.assertEqual(sorted(manager.identifier_index["localization:french"]), ["brave", "craven"])
+        self.assertEqual(sorted(manager.define_table["localization"]["<loc:french>"]), ["brave", "craven"])
+        self.assertEqual(manager.loc_map("french"), {"brave": "Courageux", "craven": "Lâche"})
+
+    def test_languages_per_instance(self):
+        manager = ModManager()
+        manager.languages.append("french")
+        self.assertEqual(ModManager().languages, [])
+
+class SilentRemovalsTest(unittest.TestCase):
+    def test_winner_omits_identifier(self):
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 1)
+        manager = make_manager(mod_a, mod_b)
+        add_script_file(manager, mod_a, TRAITS, {"namespace": "a", "brave": {}, "craven": {}})
+        add_script_file(manager, mod_b, TRAITS, {"brave": {}})
+        self.assertEqual(manager.silent_removals(), [("craven", Path(TRAITS), "A")])
+
+class LocReplaceFolderTest(unittest.TestCase):
+    def test_replace_folder_wins(self):
+        files = [
+            ("localization/replace/english/a_l_english.yml", 'l_english:\n brave: "Replaced"\n'),
+            ("localization/english/a_l_english.yml", 'l_english:\n brave: "Original"\n'),
+        ]
+        for arrival in (files, files[::-1]):
+            mod_a = make_mod("A", 0)
+            manager = make_manager(mod_a)
+            for rel_path, text in arrival:
+                add_loc_file(manager, mod_a, rel_path, text)
+            self.assertEqual(manager.loc_map(), {"brave": "Replaced"})
+            merged = manager.identifier_index["localization"]["brave"]
+            self.assertEqual(merged.value, "Replaced")
+            self.assertEqual(merged.sources["A"].rel_path, Path(files[0][0]))
+
+
+    def test_override_chain_order(self):
+        mod_a, mod_b, mod_c = make_mod("A", 0), make_mod("B", 1), make_mod("C", 2, enabled=False)
+        manager = make_manager(mod_a, mod_b, mod_c)
+        add_loc_file(manager, mod_b, "localization/replace/english/b_l_english.yml", 'l_english:\n brave: "B replaced"\n')
+        add_loc_file(manager, mod_b, "localization/english/b_l_english.yml", 'l_english:\n brave: "B"\n')
+        add_loc_file(manager, mod_a, "localization/english/a_l_english.yml", 'l_english:\n brave: "A"\n craven: "A"\n')
+        add_loc_file(manager, mod_c, "localization/english/c_l_english.yml", 'l_english:\n brave: "C"\n')
+        chain = manager.loc_override_chain("brave")
+        self.assertEqual([(name, value) for name, value, _ in chain], [("A", "A"), ("B", "B"), ("B", "B replaced")])
+        self.assertEqual(chain[-1][2], mod_b.path/"localization/replace/english/b_l_english.yml")
+        self.assertEqual(chain[-1][1], manager.loc_map()["brave"])
+        self.assertEqual(manager.loc_override_chain("missing"), [])
+
+class ExtractPerModTest(unittest.TestCase):
+    def test_trees_hold_own_definitions(self):
+        root = make_temp_dir(self)
+        mods = [make_mod("A", 0, root=root), make_mod("B", 1, root=root)]
+        write_files(mods[0].path, {"localization/english/a_l_english.yml": '\ufeffl_english:\n shared: "A"\n only_a: "A"\n'})
+        write_files(mods[1].path, {"localization/english/a_l_english.yml": '\ufeffl_english:\n shared: "B"\n'})
+        trees = make_manager(*mods).extract_per_mod()
+        self.assertEqual(sorted(trees), ["A", "B"])
+        rel_path = "localization/english/a_l_english.yml"
+        self.assertEqual(sorted(trees["A"].get_by_dir(rel_path).keys()), ["only_a", "shared"])
+        self.assertEqual(list(trees["B"].get_by_dir(rel_path).keys()), ["shared"])
+        self.assertEqual(trees["B"].get_by_dir(rel_path)["shared"].value, "B")
+
+class ModContentBreakdownTest(unittest.TestCase):
+    def test_counts_per_directory(self):
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 1)
+        manager = make_manager(mod_a, mod_b)
+        add_script_file(manager, mod_a, TRAITS, {"brave": {}, "craven": {}})
+        add_script_file(manager, mod_a, "common/traits/01_traits.txt", {"brave": {}, "lustful": {}})
+        add_script_file(manager, mod_a, "events/a_events.txt", {"namespace": "a", "a.0001": {}})
+        add_script_file(manager, mod_b, TRAITS, {"brave": {}})
+        self.assertEqual(manager.mod_content_breakdown("A"), {"common/traits": 3, "events": 1})
+        self.assertEqual(manager.mod_content_breakdown("unknown"), {})
+
+class ReplacePathTest(unittest.TestCase):
+    def test_overlapping_replace_paths(self):
+        mod_a = make_mod("A", 0, replace_path=Path("common/traits"))
+        mod_b = make_mod("B", 1, replace_paths=[Path("common/traits")])
+        mod_c = make_mod("C", 2, replace_path=Path("events"))
+        manager = make_manager(mod_a, mod_b, mod_c)
+        self.assertEqual(manager.replace_path_conflicts(), [("A", "B", Path("common/traits"))])
+
+
+    def test_lower_priority_definitions_dropped(self):
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 1, replace_path=Path("common/traits"))
+        manager = make_manager(mod_a, mod_b)
+        a_traits = add_script_file(manager, mod_a, TRAITS, {"brave": {}, "craven": {}})
+        a_events = add_script_file(manager, mod_a, "events/a_events.txt", {"a.0001": {}})
+        add_script_file(manager, mod_b, "common/traits/b_traits.txt", {"brave": {}})
+        manager.recompute_conflicts()
+        self.assertEqual(sorted(manager.identifier_index["common/traits"]), ["brave"])
+        self.assertEqual(list(manager.identifier_index["common/traits"]["brave"].sources), ["B"])
+        self.assertEqual(a_traits.replaced_by, "B")
+        self.assertIn("a.0001", manager.identifier_index["events"])
+        self.assertIsNone(getattr(a_events, "replaced_by", None))
+        self.assertEqual(manager.conflict_issues, {})
+
+@unittest.skipUnless(PARSER_AVAILABLE, "tree-sitter-paradox grammar not available")
+class TruncationTest(unittest.TestCase):
+    def extract(self, max_def_depth: int) -> ModManager:
+        mod_a = make_mod("A", 0, root=make_temp_dir(self))
+        write_files(mod_a.path, {
+            "common/traits/deep.txt": "brave = {\n\tflag = {\n\t\tyes = yes\n\t}\n}\n",
+            "common/traits/empty.txt": "trait_x = { }\n",
+        })
+        manager = make_manager(mod_a)
+        for name in ("deep.txt", "empty.txt"):
+            entry = make_entry(mod_a, f"common/traits/{name}")
+            _, file_node, err = ModManager._extract_file_definitions(entry, max_def_depth=max_def_depth)
+            self.assertIsNone(err)
+            manager.add_definition(entry, file_node)
+        self.mod = mod_a
+        return manager
+
+    def test_deep_file_reported(self):
+        manager = self.extract(max_def_depth=1)
+        self.assertEqual(manager.truncated_files(), [self.mod.path/"common/traits/deep.txt"])
+        self.assertTrue(manager.get_mod_definition("A", "common/traits", "brave")["flag"].truncated)
+        self.assertEqual(self.extract(max_def_depth=2).truncated_files(), [])
+
+    def test_empty_block_not_truncated(self):
+        manager = self.extract(max_def_depth=0)
+        self.assertFalse(manager.get_mod_definition("A", "common/traits", "trait_x").truncated)
+        self.assertEqual(manager.empty_definitions(["common/traits"]), [("trait_x", "A")])
+
+class ModsInDirectoryTest(unittest.TestCase):
+    def test_counts_most_first(self):
+        mod_a, mod_b, mod_c = make_mod("A", 0), make_mod("B", 1), make_mod("C", 2)
+        manager = make_manager(mod_a, mod_b, mod_c)
+        add_script_file(manager, mod_a, TRAITS, {"brave": {}})
+        add_script_file(manager, mod_b, TRAITS, {"brave": {}, "craven": {}})
+        add_script_file(manager, mod_c, "events/c_events.txt", {"c.0001": {}})
+        self.assertEqual(manager.mods_in_directory("common/traits"), [("B", 2), ("A", 1)])
+
+class DeduplicateIdenticalTest(unittest.TestCase):
+    def setUp(self):
+        self.mods = [make_mod("A", 0), make_mod("B", 1), make_mod("C", 2, enabled=False)]
+        self.manager = make_manager(*self.mods)
+        for mod, value in zip(self.mods, ("1", "1", "2")):
+            add_script_file(self.manager, mod, TRAITS, {"brave": {"prowess": value}, "craven": {"prowess": mod.name}})
+        self.manager.built_mods = [mod.name for mod in self.mods]
+        self.manager.recompute_conflicts()
+
+    def test_identical_definitions_removed(self):
+        self.assertEqual(self.manager.deduplicate_identical(), [("common/traits", "brave")])
+        self.assertEqual(list(self.manager.conflict_issues), [("common/traits", "craven")])
+        self.manager.recompute_conflicts()
+        self.assertEqual(list(self.manager.conflict_issues), [("common/traits", "craven")])
+
+    def test_rechecked_when_sources_change(self):
+        self.manager.deduplicate_identical()
+        self.manager.set_mod_enabled("C", True) # C defines brave differently
+        self.assertIn(("common/traits", "brave"), self.manager.conflict_issues)
+        self.assertEqual(self.manager.identical_definitions, set())
+
+class RelPathTest(unittest.TestCase):
+    def test_per_mod_root_dir(self):
+        mod_a = make_mod("A", 0, root="/drive_d/ck3_mods", root_dir=Path("/drive_d/ck3_mods"))
+        mod_b = make_mod("B", 1, root="/drive_e/more_mods", root_dir=Path("/drive_e/more_mods"))
+        manager = make_manager(mod_a, mod_b)
+        self.assertEqual(manager.get_rel_path(mod_a.path/TRAITS, mod_a), Path(TRAITS))
+        self.assertEqual(manager.get_rel_path(mod_b.path/"events/b.txt", mod_b), Path("events/b.txt"))
+        self.assertEqual(manager.get_rel_path(mod_b.path/"events/b.txt"), Path("events/b.txt"))
+
+
+    def test_mod_outside_known_dirs(self):
+        mod_a = make_mod("A", 0, root="/opt/custom")
+        mod_b = make_mod("B", 1, root="/opt/custom/nested") # a mod inside another mod's folder
+        manager = make_manager(mod_a, mod_b)
+        self.assertEqual(manager.get_rel_path(mod_a.path/TRAITS, mod_a), Path(TRAITS))
+        self.assertEqual(manager.get_rel_path(mod_a.path/"localization/english/a_l_english.yml"), Path("localization/english/a_l_english.yml"))
+        self.assertEqual(manager.get_rel_path(mod_b.path/"events/b.txt"), Path("events/b.txt"))
+        self.assertIsNone(manager.get_rel_path("/elsewhere/events/c.txt"))
+
+    def test_built_tree_nested(self):
+        mod_a = make_mod("A", 0, root=make_temp_dir(self))
+        write_files(mod_a.path, {"localization/english/a_l_english.yml": '\ufeffl_english:\n brave: "Brave"\n'})
+        manager = make_manager(mod_a)
+        manager.build_file_tree()
+        (file_node,) = manager.parsed_files["A"]
+        self.assertEqual(file_node.rel_dir, Path("localization/english"))
+        self.assertIsNotNone(manager.define_table.get_by_dir("localization/english"))
+
+@unittest.skipUnless(PARSER_AVAILABLE, "tree-sitter-paradox grammar not available")
+class LazyValuesTest(unittest.TestCase):
+    def test_lazy_equals_eager(self):
+        mod_a = make_mod("A", 0, root=make_temp_dir(self))
+        write_files(mod_a.path, {TRAITS: "brave = {\n\tcategory = personality\n\tname = \"Brave élan\"\n\tflag = { level = 2 }\n}\n"})
+        entry = make_entry(mod_a, TRAITS)
+        _, eager, _ = ModManager._extract_file_definitions(entry, max_def_depth=-1)
+        _, lazy, _ = ModManager._extract_file_definitions(entry, max_def_depth=-1, lazy_values=True)
+        self.assertEqual(type(lazy["brave"]["category"]).__name__, "DefinitionLazyValueNode")
+        self.assertEqual(type(eager["brave"]["category"]).__name__, "DefinitionValueNode")
+        self.assertEqual(lazy.dump(), eager.dump())
+        self.assertEqual(lazy["brave"]["name"].value, '"Brave élan"')
+        self.assertEqual(lazy["brave"]["flag"]["level"].value, "2")
+
+class BraceCheckTest(unittest.TestCase):
+    def test_error_lines(self):
+        mod_a = make_mod("A", 0, root=make_temp_dir(self))
+        write_files(mod_a.path, {
+            "common/traits/ok.txt": "brave = {\n\tname = \"}\" # }\n}\n",
+            "common/traits/unclosed.txt": "brave = {\n\tcategory = personality\n\nlustful = {\n}\n",
+            "gui/extra.gui": "window = {\n}\n}\n",
+        })
+        manager = make_manager(mod_a)
+        self.assertEqual(sorted(manager.brace_check()), [
+            (mod_a.path/"common/traits/unclosed.txt", 1),
+            (mod_a.path/"gui/extra.gui", 3),
+        ])
+
+class EffectiveValueTest(unittest.TestCase):
+    def test_winner_value(self):
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 1)
+        manager = make_manager(mod_a, mod_b)
+        add_script_file(manager, mod_a, TRAITS, {"brave": {"ai_boldness": "10", "category": "personality"}})
+        add_script_file(manager, mod_b, "common/traits/b_traits.txt", {"brave": {"ai_boldness": "50"}})
+        self.assertEqual(manager.effective_value("common/traits/brave/ai_boldness"), ("50", "B"))
+        self.assertIsNone(manager.effective_value("common/traits/brave/category")) # B's brave replaces A's
+        self.assertEqual(manager.effective_value("common/traits/brave"), ("{\n    ai_boldness = 50\n}", "B"))
+        self.assertIsNone(manager.effective_value("common/traits/craven"))
+
+class OutdatedModsTest(unittest.TestCase):
+    def test_outdated_built_mods(self):
+        root = make_temp_dir(self)
+        versions = {"Old": "1.11.*", "Current": "1.12.*", "Patch": "1.12.3", "Unknown": None}
+        mods = [make_mod(name, i, root=root, supported_version=version) for i, (name, version) in enumerate(versions.items())]
+        mods.append(make_mod("Disabled", len(mods), enabled=False, root=root, supported_version="1.9.*"))
+        for mod in mods:
+            write_files(mod.path, {"descriptor.mod": f'name = "{mod.name}"\n'})
+        manager = make_manager(*mods, conflict_check_range=None)
+        manager.build_file_tree()
+        self.assertEqual(manager.outdated_mods("1.12.4"), ["Old", "Patch"])
+
+class EnrolledMetadataTest(unittest.TestCase):
+    def test_sources_carry_mod_metadata(self):
+        mod_a = make_mod("A", 0, version="2.1", tags=["Gameplay"], supported_version="1.12.*", remote_file_id="2887120253")
+        manager = make_manager(mod_a)
+        add_script_file(manager, mod_a, TRAITS, {"brave": {}})
+        enrolled = manager.get_mod_definition("A", "common/traits", "brave").sources["A"].mod
+        self.assertIs(enrolled, manager.mod_list["A"])
+        self.assertEqual((enrolled.version, enrolled.tags), ("2.1", ["Gameplay"]))
+        self.assertEqual((enrolled.supported_version, enrolled.remote_file_id), ("1.12.*", "2887120253"))
+
+class SearchTest(unittest.TestCase):
+    def test_exact_before_substring(self):
+        mod_a = make_mod("A", 0)
+        manager = make_manager(mod_a)
+        add_script_file(manager, mod_a, TRAITS, {"not_brave": {}, "brave_heart": {}, "brave": {}, "craven": {}, "lustful": {}})
+        self.assertEqual([node.name for node in manager.search("Brave")], ["brave", "brave_heart", "not_brave", "craven"])
+        self.assertEqual([node.name for node in manager.search("brave", limit=1)], ["brave"])
+
+class LocCsvTest(unittest.TestCase):
+    def test_round_trip(self):
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 1)
+        manager = make_manager(mod_a, mod_b)
+        add_loc_file(manager, mod_a, "localization/english/a_l_english.yml",
+                     'l_english:\n greeting: "Hello, $name$"\n farewell: "Bye"\n')
+        add_loc_file(manager, mod_b, "localization/english/b_l_english.yml", 'l_english:\n farewell: "See you"\n')
+        root = make_temp_dir(self)
+        manager.export_loc_csv(root/"loc.csv")
+        output = root/"localization/english/translated_l_english.yml"
+        self.assertEqual(manager.import_loc_csv(root/"loc.csv", output), 2)
+        text = output.read_text(encoding="utf-8-sig")
+        self.assertEqual(text, 'l_english:\n greeting: "Hello, $name$"\n farewell: "See you"\n')
+
+class ContentDirsOnlyTest(unittest.TestCase):
+    def test_changelog_not_parsed(self):
+        mod_a = make_mod("A", 0, root=make_temp_dir(self))
+        write_files(mod_a.path, {TRAITS: "brave = { }\n", "docs/changelog.txt": "v1.1 = fixed brave\n"})
+        for content_dirs_only, parsed in ((False, [TRAITS, "docs/changelog.txt"]), (True, [TRAITS])):
+            manager = make_manager(mod_a)
+            manager.content_dirs_only = content_dirs_only
+            entries = manager._get_mod_file_entries(mod_a)
+            self.assertEqual(sorted(entry.rel_path.as_posix() for entry in entries["txt"]), sorted(parsed))
+            if content_dirs_only:
+                self.assertEqual([entry.rel_path.as_posix() for entry in entries["other"]], ["docs/changelog.txt"])
+
+class ExtractionStatsTest(unittest.TestCase):
+    def test_stats_populated(self):
+        root = make_temp_dir(self)
+        mods = [make_mod("A", 0, root=root), make_mod("B", 1, root=root)]
+        for mod in mods:
+            write_files(mod.path, {
+                f"localization/english/{mod.name}_l_english.yml": f'\ufeffl_english:\n brave: "Brave {mod.name}"\n',
+                f"gfx/interface/{mod.name}.dds": b"DDS ",
+            })
+        manager = make_manager(*mods)
+        self.assertEqual(manager.last_extraction_stats(), {})
+        with self.assertLogs("mod_analyzer", level="INFO") as logs:
+            manager.build_file_tree()
+        stats = manager.last_extraction_stats()
+        self.assertEqual((stats["files"], stats["parsed_files"]), (4, 2))
+        self.assertEqual(stats["nodes"], 4) # a file node and `brave` per mod
+        self.assertEqual(stats["conflicts"], len(manager.conflict_identifiers))
+        self.assertEqual(list(stats["phase_seconds"]), ["collect", "other", "extract"])
+        self.assertIsNone(stats["cache_hit_rate"])
+        self.assertTrue(any("Extraction summary: 4 files (2 parsed), 4 nodes" in line for line in logs.output))
+
+class ImpactOfEnablingTest(unittest.TestCase):
+    def test_override_reported(self):
+        mod_a, mod_b, mod_c = make_mod("A", 0), make_mod("B", 1), make_mod("C", 2, enabled=False)
+        manager = make_manager(mod_a, mod_b, mod_c)
+        add_script_file(manager, mod_a, TRAITS, {"brave": {}, "lustful": {}})
+        add_script_file(manager, mod_b, "common/traits/b_traits.txt", {"brave": {}})
+        add_script_file(manager, mod_c, "common/traits/c_traits.txt", {"brave": {}, "craven": {}})
+        self.assertEqual(manager.impact_of_enabling("C"), {
+            "added": [("common/traits", "craven")],
+            "overridden": [("common/traits", "brave", "B")],
+            "conflicts": [("common/traits", "brave", ["A", "B"])],
+        })
+        self.assertFalse(manager.mod_list["C"].enabled)
+        with self.assertRaises(KeyError):
+            manager.impact_of_enabling("D")
+
+class EmptyDefinitionsTest(unittest.TestCase):
+    def test_empty_block_reported(self):
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 1)
+        manager = make_manager(mod_a, mod_b)
+        add_script_file(manager, mod_a, TRAITS, {"trait_x": {}, "brave": {"category": "personality"}})
+        add_script_file(manager, mod_b, "common/traits/b_traits.txt", {"trait_y": {}})
+        add_script_file(manager, mod_b, "common/decisions/b_decisions.txt", {"stub_decision": {}})
+        self.assertEqual(manager.empty_definitions(["common/traits"]), [("trait_x", "A"), ("trait_y", "B")])
+        self.assertEqual(sorted(manager.empty_definitions(["common"])), [("stub_decision", "B"), ("trait_x", "A"), ("trait_y", "B")])
+        self.assertEqual(manager.empty_definitions(["events"]), [])
+
+class WorkshopIdTest(unittest.TestCase):
+    def test_path_for_workshop_id(self):
+        root = make_temp_dir(self)
+        mods = [make_mod("A", 0, root=root, remote_file_id="2887120253"), make_mod("Local", 1, root=root)]
+        for mod in mods:
+            write_files(mod.path, {"descriptor.mod": f'name = "{mod.name}"\n'})
+        manager = make_manager(*mods, conflict_check_range=None)
+        manager.build_file_tree()
+        self.assertEqual(manager.path_for_workshop_id(2887120253), mods[0].path)
+        self.assertEqual(manager.path_for_workshop_id(" 2887120253 "), mods[0].path)
+        self.assertIsNone(manager.path_for_workshop_id("123"))
+        self.assertIsNone(manager.path_for_workshop_id(""))
+
+class DependenciesOnDisabledTest(unittest.TestCase):
+    def test_disabled_dependency_reported(self):
+        base = make_mod("Base", 0, enabled=False)
+        lib = make_mod("Lib", 1)
+        addon = make_mod("Addon", 2, dependencies=["Base", "Lib", "Not In List"])
+        off_addon = make_mod("Off Addon", 3, enabled=False, dependencies=["Base"])
+        manager = make_manager(base, lib, addon, off_addon)
+        self.assertEqual(manager.dependencies_on_disabled(), [("Addon", "Base")])
+        manager.mod_list["Base"].enabled = True
+        self.assertEqual(manager.dependencies_on_disabled(), [])
+
+class SetModEnabledTest(unittest.TestCase):
+    def setUp(self):
+        root = make_temp_dir(self)
+        self.mods = [make_mod(name, i, enabled=name != "C", root=root) for i, name in enumerate("ABC")]
+        loc = {"A": 'brave: "Brave"\n craven: "Craven"', "B": 'brave: "Bold"', "C": 'craven: "Coward"\n brave: "Brave"'}
+        for mod in self.mods:
+            write_files(mod.path, {f"localization/english/{mod.name}_l_english.yml": f'\ufeffl_english:\n {loc[mod.name]}\n'})
+        self.manager = make_manager(*self.mods)
+        self.manager.build_file_tree(conflict_check_range="enabled", include_disabled=True)
+
+    def assert_matches_full_recompute(self):
+        incremental = {key: sorted(sources) for key, sources in self.manager.conflict_issues.items()}
+        self.manager.recompute_conflicts()
+        self.assertEqual(incremental, {key: sorted(sources) for key, sources in self.manager.conflict_issues.items()})
+
+    def test_toggle_matches_recompute(self):
+        self.assertEqual(list(self.manager.conflict_issues), [("localization/english", "brave")])
+        self.manager.set_mod_enabled("C", True)
+        self.assertEqual(sorted(self.manager.conflict_issues), [("localization/english", "brave"), ("localization/english", "craven")])
+        self.assert_matches_full_recompute()
+        self.manager.set_mod_enabled("B", False)
+        self.assertEqual(sorted(self.manager.conflict_issues), [("localization/english", "brave"), ("localization/english", "craven")])
+        self.assert_matches_full_recompute()
+        self.manager.set_mod_enabled("A", False)
+        self.assertEqual(self.manager.conflict_issues, {})
+        self.assert_matches_full_recompute()
+
+    def test_mod_not_collected(self):
+        manager = make_manager(*self.mods)
+        manager.build_file_tree(conflict_check_range="enabled") # C is disabled and skipped
+        with self.assertRaises(ValueError):
+            manager.set_mod_enabled("C", True)
+        self.assertFalse(manager.mod_list["C"].enabled)
+        with self.assertRaises(KeyError):
+            manager.set_mod_enabled("D", True)
+
+    def merge_replace_path_mods(self, b_enabled: bool) -> ModManager:
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 1, enabled=b_enabled, replace_path=Path("common/traits"))
+        mod_c = make_mod("C", 2)
+        manager = make_manager(mod_a, mod_b, mod_c)
+        add_script_file(manager, mod_a, TRAITS, {"brave": {"category": "personality"}, "craven": {}})
+        add_script_file(manager, mod_a, "events/a_events.txt", {"a.0001": {}})
+        add_script_file(manager, mod_b, "common/traits/b_traits.txt", {"brave": {"category": "fame"}})
+        add_script_file(manager, mod_c, "common/traits/c_traits.txt", {"craven": {"category": "fame"}})
+        manager.recompute_conflicts()
+        return manager
+
+    def assert_same_merge(self, manager: ModManager, expected: ModManager):
+        for index_key, def_node in expected.identifier_index.items():
+            self.assertEqual({key: sorted(node.sources) for key, node in manager.identifier_index[index_key].items()},
+                             {key: sorted(node.sources) for key, node in def_node.items()})
+        self.assertEqual({key: sorted(sources) for key, sources in manager.conflict_issues.items()},
+                         {key: sorted(sources) for key, sources in expected.conflict_issues.items()})
+        self.assertEqual({name: [node.replaced_by for node in nodes] for name, nodes in manager.parsed_files.items()},
+                         {name: [node.replaced_by for node in nodes] for name, nodes in expected.parsed_files.items()})
+
+    def test_toggle_replace_path_mod(self):
+        manager = self.merge_replace_path_mods(b_enabled=True)
+        self.assertEqual(manager.conflict_issues, {})
+        manager.set_mod_enabled("B", False)
+        self.assertEqual(sorted(manager.identifier_index["common/traits"]), ["brave", "craven"])
+        self.assertIsNone(manager.parsed_files["A"][0].replaced_by)
+        self.assertEqual(list(manager.conflict_issues), [("common/traits", "craven")])
+        self.assert_same_merge(manager, self.merge_replace_path_mods(b_enabled=False))
+        manager.set_mod_enabled("B", True)
+        self.assertEqual(sorted(manager.identifier_index["common/traits"]), ["brave", "craven"])
+        self.assertEqual(list(manager.identifier_index["common/traits"]["craven"].sources), ["C"])
+        self.assertEqual(manager.parsed_files["A"][0].replaced_by, "B")
+        self.assertEqual(manager.conflict_issues, {})
+        self.assert_same_merge(manager, self.merge_replace_path_mods(b_enabled=True))
+
+class UnusedLocKeysTest(unittest.TestCase):
+    def test_only_unreferenced_reported(self):
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 1)
+        manager = make_manager(mod_a, mod_b)
+        add_script_file(manager, mod_a, TRAITS, {"brave": {"desc": "brave_custom_desc"}})
+        add_script_file(manager, mod_a, "events/a_events.txt", {"a.0001": {"title": "a.0001.t"}})
+        add_loc_file(manager, mod_a, "localization/english/a_l_english.yml", "l_english:\n"
+                     ' trait_brave: "Brave"\n brave_custom_desc: "Fearless"\n a.0001.t: "Title $shared$"\n'
+                     ' shared: "Shared"\n dead_string: "Unused"\n')
+        add_loc_file(manager, mod_b, "localization/english/b_l_english.yml", 'l_english:\n old_key: "Removed"\n')
+        self.assertEqual(manager.unused_loc_keys(), [("dead_string", "A"), ("old_key", "B")])
+        self.assertEqual(manager.unused_loc_keys(roots=["events"]),
+                         [("trait_brave", "A"), ("brave_custom_desc", "A"), ("dead_string", "A"), ("old_key", "B")])
+
+@unittest.skipUnless(PARSER_AVAILABLE, "tree-sitter-paradox grammar not available")
+class NodeBudgetTest(unittest.TestCase):
+    def test_deep_file_cut_short(self):
+        mod_a = make_mod("A", 0, root=make_temp_dir(self))
+        write_files(mod_a.path, {
+            "common/traits/deep.txt": "brave = yes\n" + "level = { " * 60 + "}" * 60 + "\n",
+            TRAITS: "craven = {\n\tcategory = personality\n}\n",
+        })
+        config = ModManagerConfig(conflict_check_range="all", max_def_depth=-1, node_budget=50)
+        manager = ModManager.with_config(config)
+        manager.mod_list = ModList([mod_a], load_order=["A"])
+        with self.assertLogs("mod_analyzer", level="WARNING") as logs:
+            manager.build()
+        self.assertTrue(any("Parsing budget exceeded" in line and "deep.txt" in line for line in logs.output))
+        files = {node.name: node for node in manager.parsed_files["A"]}
+        self.assertTrue(files["deep.txt"].budget_exceeded)
+        self.assertEqual(files["deep.txt"]["brave"].value, "yes") # partial results are kept
+        self.assertFalse(files["00_traits.txt"].budget_exceeded)
+        self.assertEqual(manager.get_mod_definition("A", "common/traits", "craven")["category"].value, "personality")
+
+class ConflictCacheTest(unittest.TestCase):
+    def test_round_trip(self):
+        mod_a, mod_b, mod_c = make_mod("A", 0), make_mod("B", 1), make_mod("C", 2)
+        manager = make_manager(mod_a, mod_b, mod_c)
+        add_script_file(manager, mod_c, TRAITS, {"brave": {"category": "fame"}})
+        add_script_file(manager, mod_a, TRAITS, {"brave": {"category": "personality"}, "craven": {}})
+        add_script_file(manager, mod_b, "common/traits/b_traits.txt", {"craven": {"category": "fame"}})
+        manager.recompute_conflicts()
+        path = make_temp_dir(self)/"conflicts.json"
+        manager.save_conflict_cache(path)
+        self.assertEqual(ModManager.load_conflict_cache(path), manager.conflict_summary())
+        self.assertEqual(ModManager.load_conflict_cache(path), {
+            "common/traits::brave": {"mods": ["A", "C"], "winner": "C"},
+            "common/traits::craven": {"mods": ["A", "B"], "winner": "B"},
+        })
+
+class ParseFailuresTest(unittest.TestCase):
+    def setUp(self):
+        self.mod = make_mod("A", 0, root=make_temp_dir(self))
+        write_files(self.mod.path, {TRAITS: "brave = {\n\tcategory = personality\n}\n", "events/broken.txt": "a.0001 = {\n\ttitle = {\n}\n"})
+        (self.mod.path/"common/traits/missing.txt").symlink_to(self.mod.path/"common/traits/gone.txt")
+        self.manager = make_manager(self.mod, conflict_check_range=None)
+        with self.assertLogs("mod_analyzer", level="ERROR"):
+            self.manager.build_file_tree()
+
+    def test_read_failure_reported(self):
+        failures = dict(self.manager.parse_failures())
+        self.assertIn(self.mod.path/"common/traits/missing.txt", failures)
+        self.assertIn("No such file", failures[self.mod.path/"common/traits/missing.txt"])
+        self.assertNotIn(self.mod.path/TRAITS, failures)
+
+    @unittest.skipUnless(PARSER_AVAILABLE, "tree-sitter-paradox grammar not available")
+    def test_syntax_error_reported(self):
+        self.assertIn((self.mod.path/"events/broken.txt", "syntax error"), self.manager.parse_failures())
+
+class EffectiveFileTreeTest(unittest.TestCase):
+    def test_replaced_files_hidden(self):
+        root = make_temp_dir(self)
+        mod_a, mod_b = make_mod("A", 0, root=root), make_mod("B", 1, root=root, replace_path=Path("gui"))
+        write_files(mod_a.path, {"gui/shared.gui": "window = { }\n", "gui/a.gui": "window = { }\n", "gfx/a.dds": b"DDS "})
+        write_files(mod_b.path, {"gui/shared.gui": "window = { }\n", "gui/b.gui": "window = { }\n"})
+        manager = make_manager(mod_a, mod_b, conflict_check_range=None)
+        manager.build_file_tree()
+
+        self.assertEqual(sorted(manager.file_tree().get_by_dir("gui")), ["a.gui", "b.gui", "shared.gui"])
+        effective = manager.file_tree(effective=True)
+        gui = effective.get_by_dir("gui")
+        self.assertEqual(sorted(gui), ["b.gui", "shared.gui"])
+        self.assertEqual(list(gui["shared.gui"].sources), ["B"])
+        self.assertEqual(gui.replaced_by, "B")
+        self.assertEqual(list(effective.get_by_dir("gfx")), ["a.dds"])
+        self.assertIsNone(getattr(effective.get_by_dir("gfx"), "replaced_by", None))
+
+class VersionConsistencyTest(unittest.TestCase):
+    def test_tuple_populated(self):
+        mod_a = make_mod("A", 0, root=make_temp_dir(self), version="1.0")
+        write_files(mod_a.path, {TRAITS: "brave = { }\n"})
+        manager = make_manager(mod_a, make_mod("Missing", 1, root="/nowhere", version="2.0"))
+        (name, version, fingerprint), missing = manager.version_consistency()
+        self.assertEqual((name, version), ("A", "1.0"))
+        self.assertIsInstance(fingerprint, int)
+        self.assertNotEqual(fingerprint, 0)
+        self.assertEqual(missing, ("Missing", "2.0", 0))
+
+        write_files(mod_a.path, {"descriptor.mod": 'name = "A"\n'})
+        self.assertEqual(manager.version_consistency()[0][2], fingerprint) # descriptors are excluded
+        write_files(mod_a.path, {TRAITS: "brave = { category = fame }\n"})
+        self.assertNotEqual(manager.version_consistency()[0][2], fingerprint)
+
+class IterConflictsTest(unittest.TestCase):
+    def test_count_and_sample(self):
+        mod_a, mod_b, mod_c = make_mod("A", 0), make_mod("B", 1), make_mod("C", 2)
+        manager = make_manager(mod_a, mod_b, mod_c)
+        add_script_file(manager, mod_a, TRAITS, {"brave": {}, "craven": {}, "lustful": {}})
+        add_script_file(manager, mod_b, "common/traits/b_traits.txt", {"brave": {}, "craven": {}})
+        add_script_file(manager, mod_c, "common/traits/c_traits.txt", {"brave": {}})
+        manager.recompute_conflicts()
+        conflicts = {path: (winner, losers) for path, winner, losers in manager.iter_conflicts()}
+        self.assertEqual(len(conflicts), 2)
+        self.assertEqual(conflicts["common/traits::brave"], ("C", ["A", "B"]))
+        self.assertEqual(conflicts["common/traits::craven"], ("B", ["A"]))
+
+class SuspectedTyposTest(unittest.TestCase):
+    def test_near_miss_flagged(self):
+        mod_a, mod_b, mod_c = make_mod("A", 0), make_mod("B", 1), make_mod("C", 2)
+        manager = make_manager(mod_a, mod_b, mod_c)
+        add_script_file(manager, mod_a, TRAITS, {"martial_genius": {}, "lustful": {}})
+        add_script_file(manager, mod_b, "common/traits/b_traits.txt", {"martial_genius": {}, "craven": {}})
+        add_script_file(manager, mod_c, "common/traits/c_traits.txt", {"martail_genius": {}, "brave": {"opposites": ["craven"]}})
+        self.assertEqual(manager.suspected_typos("common/traits"), [("martail_genius", "martial_genius")])
+        self.assertEqual(manager.suspected_typos("common/traits", threshold=1.0), [])
+
+class ParseCacheTest(unittest.TestCase):
+    def setUp(self):
+        root = make_temp_dir(self)
+        self.cache_dir = root/"cache"
+        self.mods = [make_mod("A", 0, root=root/"mods"), make_mod("B", 1, root=root/"mods")]
+        for mod in self.mods:
+            write_files(mod.path, {f"localization/english/{mod.name}_l_english.yml": f'\ufeffl_english:\n brave: "Brave {mod.name}"\n'})
+
+    def build(self) -> ModManager:
+        manager = ModManager(cache_dir=self.cache_dir)
+        manager.mod_list = ModList(self.mods, load_order=[mod.name for mod in self.mods])
+        manager.build()
+        return manager
+
+    def test_second_build_hits_cache(self):
+        first = self.build()
+        self.assertEqual(first.last_extraction_stats()["cache_hit_rate"], 0.0)
+        second = self.build()
+        self.assertEqual(second.last_extraction_stats()["cache_hit_rate"], 1.0)
+        self.assertTrue(all(node.from_cache for nodes in second.parsed_files.values() for node in nodes))
+        self.assertEqual(second.loc_map(), first.loc_map())
+        self.assertEqual(second.parsed_files["B"][0]["brave"].sources["B"].mod, self.mods[1])
+
+        write_files(self.mods[0].path, {"localization/english/A_l_english.yml": '\ufeffl_english:\n brave: "Changed value"\n'})
+        self.assertEqual(self.build().last_extraction_stats()["cache_hit_rate"], 0.5)
+
+    def test_unwritable_cache_dir(self):
+        write_files(self.cache_dir.parent, {"cache": "not a directory"}) # the cache can't be created
+        manager = self.build()
+        self.assertEqual(manager.parse_failures(), [])
+        self.assertEqual(manager.loc_map(), {"brave": "Brave B"})
+        self.assertEqual(sorted(manager.parsed_files), ["A", "B"])
+
+class PotentialFixesTest(unittest.TestCase):
+    def test_disabled_compatch_suggested(self):
+        mod_a, mod_b = make_mod("A", 0), make_mod("B", 1)
+        compatch = make_mod("A + B Compatch", 2, enabled=False, replaces=["A", "B"])
+        traits_patch = make_mod("Traits Overhaul", 3, enabled=False, replace_path=Path("common/traits"))
+        unrelated = make_mod("Unrelated", 4, enabled=False, replaces=["A"])
+        manager = make_manager(mod_a, mod_b, compatch, traits_patch, unrelated)
+        add_script_file(manager, mod_a, TRAITS, {"brave": {}})
+        add_script_file(manager, mod_b, "common/traits/b_traits.txt", {"brave": {}})
+        add_script_file(manager, mod_a, "events/a_events.txt", {"a.0001": {}})
+        add_script_file(manager, mod_b, "events/b_events.txt", {"a.0001": {}})
+        manager.recompute_conflicts()
+        self.assertEqual(sorted(manager.potential_fixes()), [
+            ("common/traits::brave", "A + B Compatch"),
+            ("common/traits::brave", "Traits Overhaul"),
+            ("events::a.0001", "A + B Compatch"),
+        ])
+
+if __name__ == "__main__":
+    unittest.main()
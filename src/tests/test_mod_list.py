@@ -0,0 +1,185 @@
+import io
+import os
+import unittest
+from contextlib import redirect_stdout
+from types import SimpleNamespace
+
+from mod_analyzer.mod import ModList, DefinitionFileNode, DefinitionDirectoryNode
+from mod_analyzer.mod.mod_list import DefinitionIdentifierNode, DefinitionValueNode, DefinitionLazyValueNode
+from tests.helpers import make_mod, make_entry, make_temp_dir, write_files
+
+def make_traits_file() -> DefinitionFileNode:
+    """Builds `common/traits/00_traits.txt` with brave, craven and lustful, brave has a category."""
+    root = DefinitionFileNode("00_traits.txt", "common/traits")
+    for name in ("brave", "craven", "lustful"):
+        root[name] = DefinitionIdentifierNode(name, "common/traits")
+    root["brave"]["category"] = DefinitionValueNode("category", "common/traits", value="personality")
+    return root
+
+class ModListTest(unittest.TestCase):
+    def test_strict_rejects_missing_path(self):
+        incomplete = SimpleNamespace(name="A", enabled=True, load_order=0)
+        with self.assertRaisesRegex(AttributeError, "'path'"):
+            ModList([make_mod("B", 0), incomplete], strict=True)
+
+    def test_strict_accepts_complete_mods(self):
+        mod_list = ModList([make_mod("A", 0), make_mod("B", 1)], strict=True)
+        self.assertEqual(list(mod_list.keys()), ["A", "B"])
+
+class DefinitionNodeTest(unittest.TestCase):
+    def test_get_sources_load_order(self):
+        mods = [make_mod("C", 0), make_mod("A", 1), make_mod("B", 2)]
+        orders = []
+        for arrival in (mods, mods[::-1], [mods[1], mods[2], mods[0]]):
+            node = DefinitionIdentifierNode("brave", "common/traits")
+            for mod in arrival:
+                node.set_source(make_entry(mod, "common/traits/00_traits.txt"))
+            orders.append([source.name for source in node.get_sources()])
+        self.assertEqual(orders, [["C", "A", "B"]] * 3)
+
+    def test_source_mods_info(self):
+        mod_a, mod_b = make_mod("A", 4, enabled=False), make_mod("B", 1)
+        node = DefinitionIdentifierNode("brave", "common/traits")
+        for mod in (mod_a, mod_b):
+            node.set_source(make_entry(mod, "common/traits/00_traits.txt"))
+        self.assertEqual(node.source_mods_info(), [("B", 1, True), ("A", 4, False)])
+
+    def test_new_nodes_batch(self):
+        root = DefinitionFileNode("00_traits.txt", "common/traits")
+        nodes = root.new_nodes((f"trait_{i}", "common/traits", None) for i in range(1000))
+        self.assertEqual(len(nodes), 1000)
+        self.assertEqual(len(root), 1000)
+        self.assertIs(root["trait_500"], nodes[500])
+        self.assertTrue(all(node.type == "identifier" and node.parent is root for node in nodes))
+
+    def test_set_type_directory(self):
+        root = DefinitionDirectoryNode("root", "./")
+        node = root["common"] = DefinitionIdentifierNode("common", "common")
+        node["traits"] = DefinitionIdentifierNode("traits", "common/traits")
+        node.set_type("directory")
+        self.assertEqual(node.type, "directory")
+
+        file_node = DefinitionFileNode("00_traits.txt", "common/traits")
+        file_node["brave"] = DefinitionIdentifierNode("brave", "common/traits")
+        with self.assertRaises(ValueError): # a directory can't be inside a file
+            file_node["brave"].set_type("directory")
+        with self.assertRaises(ValueError): # a value can't have children
+            node.set_type("value")
+        with self.assertRaises(ValueError):
+            node.set_type("folder")
+        self.assertEqual(file_node["brave"].type, "identifier")
+
+    def test_dump(self):
+        mod = make_mod("A", 0)
+        file_node = DefinitionFileNode("00_traits.txt", "common/traits", source=make_entry(mod, "common/traits/00_traits.txt"))
+        file_node["brave"] = DefinitionIdentifierNode("brave", "common/traits")
+        file_node["brave"]["category"] = DefinitionValueNode("category", "common/traits", value="personality")
+        self.assertEqual(file_node.dump(), "\n".join([
+            "[file] 00_traits.txt (sources: A)",
+            "    [identifier] brave",
+            "        [value] category = 'personality'",
+        ]))
+
+    def test_child_entries_order(self):
+        root = make_traits_file()
+        root["abrasive"] = DefinitionIdentifierNode("abrasive", "common/traits")
+        self.assertEqual([key for key, _ in root.items()], ["brave", "craven", "lustful", "abrasive"])
+        for key, node in root.items():
+            self.assertIs(root[key], node)
+            self.assertEqual(node.name, key)
+        self.assertEqual(root.child_entries(), list(root.items()))
+
+    def test_export_graphviz(self):
+        root = make_traits_file()
+        self.assertEqual(root.export_graphviz(), "\n".join([
+            "digraph definitions {",
+            '    n0 [label="00_traits.txt:file"];',
+            "    n0 -> n1;",
+            "    n0 -> n2;",
+            "    n0 -> n3;",
+            '    n1 [label="brave:identifier"];',
+            "    n1 -> n4;",
+            '    n4 [label="category:value"];',
+            '    n2 [label="craven:identifier"];',
+            '    n3 [label="lustful:identifier"];',
+            "}",
+        ]) + "\n")
+        self.assertNotIn("->", root.export_graphviz(max_depth=0))
+
+    def test_format_tree(self):
+        root = make_traits_file()
+        self.assertEqual(root.format_tree(), "\n".join([
+            "brave: ",
+            "    category: personality",
+            "craven: ",
+            "lustful: ",
+        ]))
+        self.assertEqual(root["brave"].format_tree(indent=1), "    category: personality")
+        self.assertEqual(root["craven"].format_tree(), "")
+
+    def test_siblings(self):
+        root = make_traits_file()
+        self.assertEqual([node.name for node in root["craven"].siblings()], ["brave", "lustful"])
+        self.assertEqual(root.siblings(), [])
+
+    def test_to_standalone_tree(self):
+        mod = make_mod("A", 0)
+        root = make_traits_file()
+        root["brave"].set_source(make_entry(mod, "common/traits/00_traits.txt"))
+        clone = root["brave"].to_standalone_tree()
+        self.assertIsNone(clone.parent)
+        self.assertIsNot(clone, root["brave"])
+        self.assertEqual(clone.dump(), root["brave"].dump())
+        self.assertIs(clone["category"].parent, clone)
+        self.assertIsNone(clone.sources["A"].mod)
+        clone["category"].value = "fame"
+        self.assertEqual(root["brave"]["category"].value, "personality")
+
+    def test_json_path_round_trip(self):
+        root = DefinitionDirectoryNode("root", "./")
+        traits = root.setdefault_by_dir("common/traits", DefinitionDirectoryNode("traits", "common/traits"))
+        file_node = traits["00_traits.txt"] = make_traits_file()
+        file_node["it's"] = DefinitionIdentifierNode("it's", "common/traits")
+        category = file_node["brave"]["category"]
+        self.assertEqual(category.json_path(), "$.common.traits['00_traits.txt'].brave.category")
+        self.assertEqual(file_node["it's"].json_path(), "$.common.traits['00_traits.txt']['it\\'s']")
+        for node in (category, file_node["it's"], traits, root):
+            self.assertIs(root.resolve_json_path(node.json_path()), node)
+        self.assertIsNone(root.resolve_json_path("$.common.traits.missing"))
+        with self.assertRaises(ValueError):
+            root.resolve_json_path("common.traits")
+
+
+    def test_pretty_print_matches_format_tree(self):
+        root = make_traits_file()
+        with redirect_stdout(io.StringIO()) as out:
+            root.pretty_print()
+        self.assertEqual(out.getvalue(), root.format_tree() + "\n")
+
+class DirectoryPathTest(unittest.TestCase):
+    def test_parent_components_normalized(self):
+        root = DefinitionDirectoryNode("root", "./")
+        node = root.setdefault_by_dir("common/../common/traits", DefinitionDirectoryNode("traits", "common/traits"))
+        self.assertEqual(list(root.keys()), ["common"])
+        self.assertNotIn("..", root["common"])
+        self.assertIs(root.get_by_dir("common/traits"), node)
+        self.assertIs(root.get_by_dir("common/./traits/../traits"), node)
+
+    def test_traversal_above_root_rejected(self):
+        root = DefinitionDirectoryNode("root", "./")
+        with self.assertRaises(ValueError):
+            root.setdefault_by_dir("../outside")
+        with self.assertRaises(ValueError):
+            root.get_by_dir("common/../../outside")
+
+class LazyValueNodeTest(unittest.TestCase):
+    def test_edited_file_read_again(self):
+        file = write_files(make_temp_dir(self), {"00_traits.txt": "category = personality"})/"00_traits.txt"
+        node = DefinitionLazyValueNode("category", "common/traits", file, (11, 22))
+        self.assertEqual(node.value, "personality")
+        file.write_text("category = educational")
+        os.utime(file, ns=(0, file.stat().st_mtime_ns + 1_000_000_000)) # same size, a later mtime
+        self.assertEqual(node.value, "educational")
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,75 @@
+import unittest
+from pathlib import Path
+
+from mod_analyzer.mod import paradox_parser, paradox_loc_parser, DefinitionFileNode
+from tests.helpers import PARSER_AVAILABLE
+
+@unittest.skipUnless(PARSER_AVAILABLE, "tree-sitter-paradox grammar not available")
+class ScriptParserTest(unittest.TestCase):
+    def test_debug_parse_tree(self):
+        sexp = paradox_parser.debug_parse_tree("brave = yes")
+        self.assertTrue(sexp.startswith("(source_file"))
+        self.assertIn("assignment", sexp)
+        self.assertIn("simple_value", sexp)
+
+    def test_file_root_name(self):
+        root = paradox_parser.extract_script_definitions(
+            "brave = {\n\tcategory = personality\n}\n", root_name="00_traits.txt", rel_dir="common/traits"
+        )
+        self.assertIsInstance(root, DefinitionFileNode)
+        self.assertEqual(root.rel_dir, Path("common/traits"))
+        self.assertEqual(root["brave"].type, "identifier")
+        self.assertEqual(root["brave"]["category"].type, "value")
+
+    def test_plain_root_name(self):
+        root = paradox_parser.extract_script_definitions("brave = { }", root_name="traits")
+        self.assertEqual(root.type, "root")
+        self.assertEqual(root["brave"].type, "identifier")
+
+    def test_scripted_effect_parameters(self):
+        root = paradox_parser.extract_script_definitions(
+            "my_effect = {\n\t$TARGET$ = {\n\t\tadd_gold = $AMOUNT$\n\t}\n}\n", root_name="my_effects.txt"
+        )
+        self.assertEqual(root["my_effect"].parameters(), {"TARGET", "AMOUNT"})
+
+    def test_value_kinds(self):
+        root = paradox_parser.extract_script_definitions(
+            "brave = {\n\tcategory = personality\n\topposites = { craven lazy }\n\tcolor = hsv{ 0.5 0.5 0.5 }\n}\n"
+        )
+        brave = root["brave"]
+        self.assertEqual(brave.value_kind(), "block")
+        self.assertEqual(brave["category"].value_kind(), "scalar")
+        self.assertEqual(brave["opposites"].value_kind(), "array")
+        self.assertEqual(brave["opposites"].value, ["craven", "lazy"])
+        self.assertEqual(brave["color"].value_kind(), "tagged_array")
+
+    def test_ast_array_and_block(self):
+        ast = paradox_parser.parse_script_ast("brave = {\n\topposites = { craven }\n}\n")
+        self.assertEqual(ast["type"], "source_file")
+        brave = ast["children"][0]
+        self.assertEqual((brave["type"], brave["start"], brave["end"]), ("assignment", (0, 0), (2, 1)))
+        key, block = brave["children"]
+        self.assertEqual((key["field"], key["text"]), ("key", "brave"))
+        self.assertEqual(block["field"], "value")
+        self.assertIn(block["type"], ("statement", "map"))
+        opposites = block["children"][0]
+        array = opposites["children"][1]
+        self.assertEqual(array["type"], "array")
+        self.assertEqual((array["start"], array["end"]), ((1, 13), (1, 23)))
+
+class LocParserTest(unittest.TestCase):
+    def test_reference_columns(self):
+        root = paradox_loc_parser.extract_definitions('l_english:\n greeting: "Hello $name$ and $title|U$"\n')
+        self.assertEqual(root["greeting"].references, [("name", 6), ("title", 17)])
+
+
+    def test_comment_attached(self):
+        text = 'l_english:\n # shown on the trait tooltip\n brave: "Brave"\n # orphan note\n\n craven: "Craven"\n lustful: "Lustful"\n'
+        root = paradox_loc_parser.extract_definitions(text, attach_comments=True)
+        self.assertEqual(root["brave"].comment, "shown on the trait tooltip")
+        self.assertIsNone(root["craven"].comment) # not right above the key
+        self.assertIsNone(root["lustful"].comment)
+        self.assertIsNone(paradox_loc_parser.extract_definitions(text)["brave"].comment)
+
+if __name__ == "__main__":
+    unittest.main()